@@ -0,0 +1,208 @@
+use crate::column_type::ValueKind;
+use crate::xlsx2csv::{SheetTable, cell_name};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::{fs, io, path};
+
+/// 敏感词表名
+pub const BAD_WORDS_TABLE: &str = "BadWords";
+/// 敏感词所在的列名, 没有该列时取第二列, 只有一列时取主键列
+pub const BAD_WORDS_COLUMN: &str = "word";
+/// 预编译的敏感词自动机文件名
+pub const BAD_WORDS_AUTOMATON_FILE: &str = "BadWords.ac.json";
+
+/// 规范化文本, 客户端和服务器匹配前必须对输入做同样的处理:
+/// 全角字符转半角、转小写、去掉所有空白字符
+pub fn normalize(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\u{3000}' => ' ',
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            _ => c,
+        })
+        .flat_map(char::to_lowercase)
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// 规范化后的敏感词列表, 返回去重后的词和重复的原词; 规范化后为空的词会被忽略
+pub fn word_list(table: &SheetTable) -> (Vec<String>, Vec<String>) {
+    let column_index = table
+        .columns
+        .iter()
+        .position(|c| c.name.trim() == BAD_WORDS_COLUMN)
+        .unwrap_or(if table.columns.len() > 1 { 1 } else { 0 });
+
+    let mut seen = HashSet::new();
+    let mut words = Vec::new();
+    let mut duplicates = Vec::new();
+    for row in &table.rows {
+        let raw = &row.values[column_index];
+        // 与导出的CSV一致, 去掉双引号
+        let word = normalize(&raw.replace('"', ""));
+        if word.is_empty() {
+            continue;
+        }
+        if seen.insert(word.clone()) {
+            words.push(word);
+        } else {
+            duplicates.push(raw.clone());
+        }
+    }
+    (words, duplicates)
+}
+
+/// Aho-Corasick 自动机, 以压缩行(CSR)格式存储转移边, 便于客户端和服务器直接加载:
+///
+/// - 节点 i 的转移边为 `edge_chars[edge_start[i]..edge_start[i + 1]]`, 按码点升序, 可二分查找,
+///   对应的目标节点在 `edge_next` 的相同位置
+/// - `fail[i]` 为失配时跳转的节点, 根节点为 0
+/// - `match_len[i]` 为到达节点 i 时以当前字符结尾的最长敏感词长度(码点数), 0 表示没有匹配
+#[derive(Serialize)]
+pub struct Automaton {
+    pub version: u32,
+    pub normalize: &'static [&'static str],
+    pub words: usize,
+    pub duplicates: usize,
+    pub edge_start: Vec<u32>,
+    pub edge_chars: Vec<u32>,
+    pub edge_next: Vec<u32>,
+    pub fail: Vec<u32>,
+    pub match_len: Vec<u32>,
+}
+
+impl Automaton {
+    pub fn build(words: &[String], duplicates: usize) -> Self {
+        // 先构建字典树
+        let mut children: Vec<BTreeMap<char, u32>> = vec![BTreeMap::new()];
+        let mut word_len: Vec<u32> = vec![0];
+        for word in words {
+            let mut node = 0;
+            for c in word.chars() {
+                node = match children[node].get(&c) {
+                    Some(&next) => next as usize,
+                    None => {
+                        let next = children.len();
+                        children[node].insert(c, next as u32);
+                        children.push(BTreeMap::new());
+                        word_len.push(0);
+                        next
+                    }
+                };
+            }
+            word_len[node] = word.chars().count() as u32;
+        }
+
+        // 按层遍历计算失配指针, 父节点的失配链在子节点之前已经确定
+        let mut fail = vec![0u32; children.len()];
+        let mut match_len = word_len.clone();
+        let mut queue: VecDeque<usize> = children[0].values().map(|&n| n as usize).collect();
+        while let Some(node) = queue.pop_front() {
+            for (&c, &child) in &children[node] {
+                let child = child as usize;
+                let mut f = fail[node] as usize;
+                fail[child] = loop {
+                    if let Some(&next) = children[f].get(&c) {
+                        break next;
+                    }
+                    if f == 0 {
+                        break 0;
+                    }
+                    f = fail[f] as usize;
+                };
+                match_len[child] = match_len[child].max(match_len[fail[child] as usize]);
+                queue.push_back(child);
+            }
+        }
+
+        let mut edge_start = Vec::with_capacity(children.len() + 1);
+        let mut edge_chars = Vec::new();
+        let mut edge_next = Vec::new();
+        for node_children in &children {
+            edge_start.push(edge_chars.len() as u32);
+            for (&c, &next) in node_children {
+                edge_chars.push(c as u32);
+                edge_next.push(next);
+            }
+        }
+        edge_start.push(edge_chars.len() as u32);
+
+        Self {
+            version: 1,
+            normalize: &["halfwidth", "lowercase", "strip_whitespace"],
+            words: words.len(),
+            duplicates,
+            edge_start,
+            edge_chars,
+            edge_next,
+            fail,
+            match_len,
+        }
+    }
+
+    /// 从节点 node 读入字符 c 后到达的节点
+    fn next(&self, mut node: usize, c: char) -> usize {
+        loop {
+            let edges = self.edge_start[node] as usize..self.edge_start[node + 1] as usize;
+            if let Ok(i) = self.edge_chars[edges.clone()].binary_search(&(c as u32)) {
+                return self.edge_next[edges.start + i] as usize;
+            }
+            if node == 0 {
+                return 0;
+            }
+            node = self.fail[node] as usize;
+        }
+    }
+
+    /// 规范化后的文本中出现的敏感词, 按出现顺序去重
+    pub fn find_words(&self, text: &str) -> Vec<String> {
+        let chars: Vec<char> = normalize(text).chars().collect();
+        let mut words: Vec<String> = Vec::new();
+        let mut node = 0;
+        for (i, &c) in chars.iter().enumerate() {
+            node = self.next(node, c);
+            let len = self.match_len[node] as usize;
+            if len > 0 {
+                let word: String = chars[i + 1 - len..=i].iter().collect();
+                if !words.contains(&word) {
+                    words.push(word);
+                }
+            }
+        }
+        words
+    }
+}
+
+/// 检查表中除主键外所有字符串列是否包含敏感词, 命中时按单元格报告警告
+pub fn check_table(automaton: &Automaton, table: &SheetTable, warnings: &mut Vec<String>) {
+    let columns: Vec<usize> = (1..table.columns.len())
+        .filter(|&i| table.columns[i].column_type.kind() == ValueKind::String)
+        .collect();
+    for row in &table.rows {
+        for &i in &columns {
+            let words = automaton.find_words(&row.values[i]);
+            if words.is_empty() {
+                continue;
+            }
+            let column = &table.columns[i];
+            warnings.push(format!(
+                "<<{}>> {} {}: 包含敏感词 {} ({})",
+                table.source,
+                cell_name(column.cell_index, row.line),
+                column.name,
+                words.join(", "),
+                row.values[i]
+            ));
+        }
+    }
+}
+
+/// 为敏感词表生成预编译的自动机文件
+pub fn write_automaton(table: &SheetTable, dir: &path::Path) -> io::Result<()> {
+    let (words, duplicates) = word_list(table);
+    let automaton = Automaton::build(&words, duplicates.len());
+    fs::write(
+        dir.join(&table.output_dir).join(BAD_WORDS_AUTOMATON_FILE),
+        serde_json::to_string(&automaton)?,
+    )
+}
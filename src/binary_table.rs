@@ -0,0 +1,393 @@
+use crate::column_type::{ValueKind, parse_bool};
+use crate::export_target::{ExportTarget, HeaderStyle};
+use crate::xlsx2csv::{SheetTable, target_columns};
+use std::collections::HashMap;
+use std::{fs, io, path};
+
+/// 二进制表的扩展名, 与CSV文件输出到同一目录
+pub const BINARY_TABLE_EXTENSION: &str = "bin";
+/// 二进制表的TypeScript读取器, 每个启用了生成器的导出目标写出一份
+pub const TABLE_READER_TS_FILE: &str = "CXTableReader.ts";
+
+const MAGIC: &[u8; 4] = b"CXTB";
+const FORMAT_VERSION: u16 = 1;
+/// 超过这个范围的整数在JS中会丢失精度, 按字符串导出
+const MAX_SAFE_INTEGER: i64 = (1 << 53) - 1;
+
+/// 列数据的存储方式, 数值与读取器中的 `CXColumnKind` 一致
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Int32 = 0,
+    Float64 = 1,
+    Bool = 2,
+    String = 3,
+}
+
+enum ColumnData {
+    Int32(Vec<i32>),
+    Float64(Vec<f64>),
+    Bool(Vec<bool>),
+    /// 字符串池下标
+    String(Vec<u32>),
+}
+
+impl ColumnData {
+    fn kind(&self) -> ColumnKind {
+        match self {
+            ColumnData::Int32(_) => ColumnKind::Int32,
+            ColumnData::Float64(_) => ColumnKind::Float64,
+            ColumnData::Bool(_) => ColumnKind::Bool,
+            ColumnData::String(_) => ColumnKind::String,
+        }
+    }
+}
+
+/// 去重的字符串池, 列名、类型名和字符串单元格都存为池中的下标
+#[derive(Default)]
+struct StringPool {
+    strings: Vec<String>,
+    indexes: HashMap<String, u32>,
+}
+
+impl StringPool {
+    fn add(&mut self, value: &str) -> u32 {
+        if let Some(&index) = self.indexes.get(value) {
+            return index;
+        }
+        let index = self.strings.len() as u32;
+        self.strings.push(value.to_string());
+        self.indexes.insert(value.to_string(), index);
+        index
+    }
+}
+
+/// 按类型行把一列转换成二进制列; 整数列全部在i32范围内时存为Int32, 否则存为Float64;
+/// 有无法按类型解析的值时整列按字符串存储并返回 false
+fn column_data(values: &[&str], kind: ValueKind, pool: &mut StringPool) -> (ColumnData, bool) {
+    let parsed = match kind {
+        ValueKind::Int => values
+            .iter()
+            .map(|v| v.trim().parse::<i64>().ok())
+            .collect::<Option<Vec<i64>>>()
+            .filter(|ints| ints.iter().all(|i| i.abs() <= MAX_SAFE_INTEGER))
+            .map(
+                |ints| match ints.iter().map(|&i| i32::try_from(i)).collect() {
+                    Ok(ints) => ColumnData::Int32(ints),
+                    Err(_) => ColumnData::Float64(ints.iter().map(|&i| i as f64).collect()),
+                },
+            ),
+        ValueKind::Float => values
+            .iter()
+            .map(|v| v.trim().parse::<f64>().ok())
+            .collect::<Option<Vec<f64>>>()
+            .map(ColumnData::Float64),
+        ValueKind::Bool => values
+            .iter()
+            .map(|v| parse_bool(v.trim()))
+            .collect::<Option<Vec<bool>>>()
+            .map(ColumnData::Bool),
+        ValueKind::String => None,
+    };
+    match parsed {
+        Some(data) => (data, true),
+        None => (
+            ColumnData::String(values.iter().map(|v| pool.add(v)).collect()),
+            kind == ValueKind::String,
+        ),
+    }
+}
+
+/// 行号按主键排序, 读取器用二分查找按主键取行; 字符串按UTF-16编码比较, 与JS的字符串比较一致
+fn key_index(table: &SheetTable, key: &ColumnData) -> Vec<u32> {
+    let mut index: Vec<u32> = (0..table.rows.len() as u32).collect();
+    match key {
+        ColumnData::Int32(keys) => index.sort_by_key(|&i| keys[i as usize]),
+        ColumnData::Float64(keys) => {
+            index.sort_by(|&a, &b| keys[a as usize].total_cmp(&keys[b as usize]))
+        }
+        ColumnData::Bool(keys) => index.sort_by_key(|&i| keys[i as usize]),
+        ColumnData::String(_) => index.sort_by(|&a, &b| {
+            let (a, b) = (
+                &table.rows[a as usize].values[0],
+                &table.rows[b as usize].values[0],
+            );
+            a.encode_utf16().cmp(b.encode_utf16())
+        }),
+    }
+    index
+}
+
+/// 把一张表按导出目标的列写成二进制表, 文件结构(小端序):
+///
+/// ```text
+/// "CXTB" | u16 格式版本 | u16 列数 | u32 行数 | u32 字符串数
+/// 字符串池: 每个字符串 u32 字节数 + UTF-8
+/// 列定义: 每列 u32 列名下标 + u32 类型名下标 + u8 存储方式
+/// 列数据: 按列依次存放, Int32为i32, Float64为f64, Bool为u8, String为u32字符串下标
+/// 主键索引: u32 行号, 按主键排序
+/// ```
+///
+/// 单元格按原样写入, 不做CSV的逗号和换行转义; 无法按类型解析的列按字符串存储并报告警告
+pub fn write_binary_table(
+    table: &SheetTable,
+    target: &ExportTarget,
+    dir: &path::Path,
+    warnings: &mut Vec<String>,
+) -> io::Result<()> {
+    let mut pool = StringPool::default();
+    let mut definitions = Vec::new();
+    let mut columns = Vec::new();
+    for i in target_columns(table, target) {
+        let column = &table.columns[i];
+        let type_name = match target.header {
+            HeaderStyle::Server => &column.column_type.server,
+            HeaderStyle::Client => &column.column_type.client,
+        };
+        let values: Vec<&str> = table.rows.iter().map(|r| r.values[i].as_str()).collect();
+        let (data, typed) = column_data(&values, column.column_type.kind(), &mut pool);
+        if !typed {
+            warnings.push(format!(
+                "<<{}>> {}: 有无法按类型 {} 解析的值, 二进制表中按字符串存储",
+                table.source, column.name, column.column_type.server
+            ));
+        }
+        definitions.push((pool.add(&column.name), pool.add(type_name), data.kind()));
+        columns.push(data);
+    }
+    let index = columns
+        .first()
+        .map(|key| key_index(table, key))
+        .unwrap_or_default();
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(columns.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&(table.rows.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(pool.strings.len() as u32).to_le_bytes());
+    for string in &pool.strings {
+        bytes.extend_from_slice(&(string.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(string.as_bytes());
+    }
+    for (name, type_name, kind) in definitions {
+        bytes.extend_from_slice(&name.to_le_bytes());
+        bytes.extend_from_slice(&type_name.to_le_bytes());
+        bytes.push(kind as u8);
+    }
+    for data in &columns {
+        match data {
+            ColumnData::Int32(values) => values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes())),
+            ColumnData::Float64(values) => values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes())),
+            ColumnData::Bool(values) => bytes.extend(values.iter().map(|&v| v as u8)),
+            ColumnData::String(values) => values
+                .iter()
+                .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes())),
+        }
+    }
+    for row in index {
+        bytes.extend_from_slice(&row.to_le_bytes());
+    }
+
+    let dir = dir.join(&table.output_dir);
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join(format!("{}.{}", table.meta.name, BINARY_TABLE_EXTENSION)),
+        bytes,
+    )
+}
+
+/// 写出与二进制表格式对应的TypeScript读取器
+pub fn write_table_reader_ts(dir: &path::Path) -> io::Result<()> {
+    fs::write(dir.join(TABLE_READER_TS_FILE), TABLE_READER_TS)
+}
+
+const TABLE_READER_TS: &str = r#"/** 导表工具生成的二进制表读取器, 与导出的 .bin 文件格式对应, 请勿手动修改 */
+
+export type CXTableValue = number | boolean | string;
+
+export enum CXColumnKind {
+    Int32 = 0,
+    Float64 = 1,
+    Bool = 2,
+    String = 3,
+}
+
+export interface CXTableColumn {
+    name: string;
+    type: string;
+    kind: CXColumnKind;
+}
+
+const MAGIC = 0x42545843; // "CXTB"
+const FORMAT_VERSION = 1;
+
+const textDecoder = typeof TextDecoder !== 'undefined' ? new TextDecoder('utf-8') : null;
+
+function decodeUtf8(bytes: Uint8Array, start: number, end: number): string {
+    if (textDecoder) {
+        return textDecoder.decode(bytes.subarray(start, end));
+    }
+    const codes: number[] = [];
+    let i = start;
+    while (i < end) {
+        const b = bytes[i++];
+        if (b < 0x80) {
+            codes.push(b);
+        } else if (b < 0xe0) {
+            codes.push(((b & 0x1f) << 6) | (bytes[i++] & 0x3f));
+        } else if (b < 0xf0) {
+            codes.push(((b & 0x0f) << 12) | ((bytes[i++] & 0x3f) << 6) | (bytes[i++] & 0x3f));
+        } else {
+            const code =
+                ((b & 0x07) << 18) | ((bytes[i++] & 0x3f) << 12) | ((bytes[i++] & 0x3f) << 6) | (bytes[i++] & 0x3f);
+            codes.push(0xd7c0 + (code >> 10), 0xdc00 | (code & 0x3ff));
+        }
+    }
+    let result = '';
+    for (let j = 0; j < codes.length; j += 4096) {
+        result += String.fromCharCode.apply(null, codes.slice(j, j + 4096));
+    }
+    return result;
+}
+
+export class CXTable {
+    readonly columns: CXTableColumn[] = [];
+    readonly rowCount: number;
+    private readonly strings: string[] = [];
+    private readonly data: ArrayLike<number>[] = [];
+    private readonly columnIndexes: Record<string, number> = {};
+    private readonly keyIndex: Uint32Array;
+
+    constructor(buffer: ArrayBuffer) {
+        const view = new DataView(buffer);
+        const bytes = new Uint8Array(buffer);
+        if (view.getUint32(0, true) !== MAGIC) {
+            throw new Error('不是导表工具导出的二进制表');
+        }
+        const version = view.getUint16(4, true);
+        if (version !== FORMAT_VERSION) {
+            throw new Error(`不支持的二进制表版本: ${version}`);
+        }
+        const columnCount = view.getUint16(6, true);
+        const rowCount = view.getUint32(8, true);
+        const stringCount = view.getUint32(12, true);
+        this.rowCount = rowCount;
+        let offset = 16;
+
+        for (let i = 0; i < stringCount; i++) {
+            const length = view.getUint32(offset, true);
+            offset += 4;
+            this.strings.push(decodeUtf8(bytes, offset, offset + length));
+            offset += length;
+        }
+        for (let i = 0; i < columnCount; i++) {
+            const column: CXTableColumn = {
+                name: this.strings[view.getUint32(offset, true)],
+                type: this.strings[view.getUint32(offset + 4, true)],
+                kind: view.getUint8(offset + 8),
+            };
+            offset += 9;
+            this.columns.push(column);
+            this.columnIndexes[column.name] = i;
+        }
+        for (const column of this.columns) {
+            switch (column.kind) {
+                case CXColumnKind.Int32: {
+                    const values = new Int32Array(rowCount);
+                    for (let r = 0; r < rowCount; r++, offset += 4) values[r] = view.getInt32(offset, true);
+                    this.data.push(values);
+                    break;
+                }
+                case CXColumnKind.Float64: {
+                    const values = new Float64Array(rowCount);
+                    for (let r = 0; r < rowCount; r++, offset += 8) values[r] = view.getFloat64(offset, true);
+                    this.data.push(values);
+                    break;
+                }
+                case CXColumnKind.Bool: {
+                    this.data.push(bytes.slice(offset, offset + rowCount));
+                    offset += rowCount;
+                    break;
+                }
+                default: {
+                    const values = new Uint32Array(rowCount);
+                    for (let r = 0; r < rowCount; r++, offset += 4) values[r] = view.getUint32(offset, true);
+                    this.data.push(values);
+                    break;
+                }
+            }
+        }
+        this.keyIndex = new Uint32Array(rowCount);
+        for (let r = 0; r < rowCount; r++, offset += 4) this.keyIndex[r] = view.getUint32(offset, true);
+    }
+
+    static parse(buffer: ArrayBuffer): CXTable {
+        return new CXTable(buffer);
+    }
+
+    /** 列名对应的列下标, 不存在时为 -1 */
+    columnIndex(name: string): number {
+        const index = this.columnIndexes[name];
+        return index === undefined ? -1 : index;
+    }
+
+    value(row: number, column: number | string): CXTableValue {
+        const index = typeof column === 'number' ? column : this.columnIndex(column);
+        const raw = this.data[index][row];
+        switch (this.columns[index].kind) {
+            case CXColumnKind.Bool:
+                return raw !== 0;
+            case CXColumnKind.String:
+                return this.strings[raw];
+            default:
+                return raw;
+        }
+    }
+
+    row(row: number): Record<string, CXTableValue> {
+        const record: Record<string, CXTableValue> = {};
+        for (let i = 0; i < this.columns.length; i++) {
+            record[this.columns[i].name] = this.value(row, i);
+        }
+        return record;
+    }
+
+    rows(): Record<string, CXTableValue>[] {
+        const records: Record<string, CXTableValue>[] = [];
+        for (let r = 0; r < this.rowCount; r++) {
+            records.push(this.row(r));
+        }
+        return records;
+    }
+
+    /** 按主键(第一列)二分查找行号, 找不到时为 -1 */
+    findRow(key: CXTableValue): number {
+        let low = 0;
+        let high = this.rowCount - 1;
+        while (low <= high) {
+            const middle = (low + high) >> 1;
+            const row = this.keyIndex[middle];
+            const current = this.value(row, 0);
+            if (current === key) {
+                return row;
+            }
+            const less = typeof current === 'string' ? current < String(key) : Number(current) < Number(key);
+            if (less) {
+                low = middle + 1;
+            } else {
+                high = middle - 1;
+            }
+        }
+        return -1;
+    }
+
+    getByKey(key: CXTableValue): Record<string, CXTableValue> | undefined {
+        const row = this.findRow(key);
+        return row < 0 ? undefined : this.row(row);
+    }
+}
+"#;
@@ -0,0 +1,96 @@
+use crate::file_utils::list_files_recursive;
+use crate::xlsx2csv::{SheetTable, cell_name};
+use std::collections::HashSet;
+use std::{io, path};
+
+/// 客户端资源目录, 相对客户端工程目录
+pub const CLIENT_ASSETS_DIR: &str = "assets";
+/// 资源路径依次在这些目录下查找, 相对资源目录; 动态加载的资源路径相对 resources 目录
+const ASSET_ROOTS: &[&str] = &["", "resources"];
+
+/// 资源类型对应的文件扩展名, 单元格中的路径不带扩展名时依次尝试;
+/// 未知的资源类型只按单元格中的路径原样查找
+fn asset_extensions(asset: &str) -> &'static [&'static str] {
+    match asset {
+        "texture" | "sprite" => &["png", "jpg", "jpeg", "webp"],
+        "prefab" => &["prefab"],
+        "spine" => &["json", "skel"],
+        "audio" => &["mp3", "ogg", "wav", "m4a"],
+        "font" => &["ttf", "fnt"],
+        _ => &[],
+    }
+}
+
+/// 检查所有 `asset:类型` 列引用的资源文件在客户端工程中是否存在, 路径区分大小写;
+/// 客户端资源目录不存在时只警告, 不检查
+pub fn check_asset_references(
+    tables: &[SheetTable],
+    client_dir: &path::Path,
+    errors: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> io::Result<()> {
+    let has_asset_columns = tables
+        .iter()
+        .any(|t| t.columns.iter().any(|c| c.column_type.asset.is_some()));
+    if !has_asset_columns {
+        return Ok(());
+    }
+
+    let assets_dir = client_dir.join(CLIENT_ASSETS_DIR);
+    if !assets_dir.is_dir() {
+        warnings.push(format!(
+            "客户端资源目录 {} 不存在, 跳过资源引用检查",
+            assets_dir.display()
+        ));
+        return Ok(());
+    }
+    let files: HashSet<String> = list_files_recursive(&assets_dir, |path| {
+        path.extension().is_none_or(|ext| ext != "meta")
+    })?
+    .into_iter()
+    .collect();
+
+    for table in tables {
+        for (i, column) in table.columns.iter().enumerate() {
+            let Some(asset) = &column.column_type.asset else {
+                continue;
+            };
+            for row in &table.rows {
+                let value = row.values[i].trim();
+                if value.is_empty() || asset_exists(&files, asset, value) {
+                    continue;
+                }
+                errors.push(format!(
+                    "<<{}>> {} {}: 找不到{}资源 {}",
+                    table.source,
+                    cell_name(column.cell_index, row.line),
+                    column.name,
+                    asset,
+                    value
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn asset_exists(files: &HashSet<String>, asset: &str, value: &str) -> bool {
+    // Cocos 中图片常以 `icons/a/spriteFrame` 的子资源形式引用
+    let value = value.trim_start_matches('/');
+    let value = value
+        .strip_suffix("/spriteFrame")
+        .or_else(|| value.strip_suffix("/texture"))
+        .unwrap_or(value);
+
+    ASSET_ROOTS.iter().any(|root| {
+        let path = if root.is_empty() {
+            value.to_string()
+        } else {
+            format!("{}/{}", root, value)
+        };
+        files.contains(&path)
+            || asset_extensions(asset)
+                .iter()
+                .any(|ext| files.contains(&format!("{}.{}", path, ext)))
+    })
+}
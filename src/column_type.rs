@@ -0,0 +1,115 @@
+/// 类型行(第3行)单元格解析结果
+///
+/// 格式: `服务器类型[#客户端类型][|default=默认值][|required][|targets=目标1+目标2][|width=显示宽度]`,
+/// 例如 `int#number|default=1`、`string|required|targets=server+gm-web`;
+/// 类型写作 `asset:texture` 时按字符串导出, 并检查引用的客户端资源是否存在
+#[derive(Default, Clone, Debug)]
+pub struct ColumnType {
+    pub server: String,
+    pub client: String,
+    pub default: Option<String>,
+    pub required: bool,
+    /// 标记的导出目标, 未标记时由导出目标的列过滤方式决定
+    pub targets: Option<Vec<String>>,
+    /// `asset:类型` 声明的客户端资源类型, 见 `client_assets`
+    pub asset: Option<String>,
+    /// 文本的最大显示宽度, 见 `text_width`
+    pub width: Option<usize>,
+}
+
+impl ColumnType {
+    pub fn parse(cell: &str) -> Self {
+        let mut parts = cell.split('|');
+        let types = parts.next().unwrap_or_default();
+        let (server, client) = types.split_once('#').unwrap_or((types, types));
+
+        let (server, server_asset) = split_asset_type(server);
+        let (client, client_asset) = split_asset_type(client);
+
+        let mut column_type = Self {
+            server,
+            client,
+            asset: server_asset.or(client_asset),
+            ..Default::default()
+        };
+        for option in parts {
+            let option = option.trim();
+            if option == "required" {
+                column_type.required = true;
+            } else if let Some(default) = option.strip_prefix("default=") {
+                column_type.default = Some(default.to_string());
+            } else if let Some(width) = option.strip_prefix("width=") {
+                column_type.width = width.trim().parse().ok();
+            } else if let Some(targets) = option.strip_prefix("targets=") {
+                column_type.targets = Some(
+                    targets
+                        .split('+')
+                        .map(|target| target.trim().to_string())
+                        .filter(|target| !target.is_empty())
+                        .collect(),
+                );
+            }
+        }
+        column_type
+    }
+
+    /// 按服务器类型归类的值类型
+    pub fn kind(&self) -> ValueKind {
+        match self.server.trim().to_ascii_lowercase().as_str() {
+            "int" | "long" | "short" | "byte" => ValueKind::Int,
+            "float" | "double" | "number" => ValueKind::Float,
+            "bool" | "boolean" => ValueKind::Bool,
+            _ => ValueKind::String,
+        }
+    }
+
+    /// 未声明默认值时按类型取的默认值
+    pub fn type_default(&self) -> &'static str {
+        match self.kind() {
+            ValueKind::Int | ValueKind::Float => "0",
+            ValueKind::Bool => "false",
+            ValueKind::String => "",
+        }
+    }
+
+    /// 按类型把单元格文本转换成json值, 无法转换时保留字符串
+    pub fn json_value(&self, value: &str) -> serde_json::Value {
+        let trimmed = value.trim();
+        match self.kind() {
+            ValueKind::Int => trimmed.parse::<i64>().map(Into::into).ok(),
+            ValueKind::Float => trimmed
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(serde_json::Value::Number),
+            ValueKind::Bool => parse_bool(trimmed).map(Into::into),
+            ValueKind::String => None,
+        }
+        .unwrap_or_else(|| value.into())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    Int,
+    Float,
+    Bool,
+    String,
+}
+
+/// `asset:texture` 按字符串类型导出, 返回导出的类型名和资源类型
+fn split_asset_type(name: &str) -> (String, Option<String>) {
+    match name.trim().strip_prefix("asset:") {
+        Some(asset) => ("string".to_string(), Some(asset.trim().to_string())),
+        None => (name.to_string(), None),
+    }
+}
+
+/// 布尔单元格兼容 true/false 和 1/0
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
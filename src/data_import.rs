@@ -0,0 +1,1105 @@
+use crate::column_type::ValueKind;
+use crate::export_diff::RowChange;
+use crate::xlsx2csv::{SheetTable, Xlsx2CsvTool, cell_name};
+use calamine::{Reader, Xlsx, open_workbook};
+use eframe::egui;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader as XmlReader, Writer};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, Error, Read, Write};
+use std::path::Path;
+use std::time::Duration;
+
+/// 导入文件中的数据, 第一列为主键; JSON对象缺少的字段为 None, 不修改对应单元格
+struct ImportData {
+    columns: Vec<String>,
+    rows: Vec<Vec<Option<String>>>,
+    warnings: Vec<String>,
+}
+
+/// 写入单元格的值, 数值列中能解析为数字的值写为数字, 其他写为字符串
+struct CellValue {
+    text: String,
+    numeric: bool,
+}
+
+/// 一个文件的导入结果
+pub struct DataImportReport {
+    pub file: String,
+    /// 工作簿相对Excel目录的路径和工作表名
+    pub workbook: String,
+    pub sheet: String,
+    pub updated: Vec<RowChange>,
+    pub added: Vec<String>,
+    pub unchanged: usize,
+    pub warnings: Vec<String>,
+}
+
+fn import_error(message: String) -> Error {
+    Error::new(io::ErrorKind::Other, message)
+}
+
+/// 按RFC 4180解析CSV, 支持引号内的逗号、换行和双引号转义, 忽略空行
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => quoted = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => quoted = true,
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' | '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows.retain(|row| row.iter().any(|cell| !cell.is_empty()));
+    rows
+}
+
+/// CSV的表头为第一个以主键字段名开头的行, 之前的行(导出的注释行)忽略,
+/// 紧跟的类型行(与工作表类型行的主键类型相同)也忽略; 有类型行时是本工具导出的CSV,
+/// 还原 `escape_text` 转义的换行, 全角逗号无法区分是否由半角逗号转义而来, 保持原样并警告
+fn csv_data(content: &str, table: &SheetTable) -> io::Result<ImportData> {
+    let key = &table.columns[0];
+    let mut rows = parse_csv(content).into_iter();
+    rows.by_ref()
+        .find(|row| row[0].trim() == key.name)
+        .map(|header| {
+            let columns: Vec<String> = header.iter().map(|name| name.trim().to_string()).collect();
+            let mut rows = rows.peekable();
+            let exported = rows.peek().is_some_and(|row| {
+                let cell = row[0].trim();
+                cell == key.column_type.server || cell == key.column_type.client
+            });
+            if exported {
+                rows.next();
+            }
+            let mut full_width_commas = 0;
+            let rows = rows
+                .map(|row| {
+                    (0..columns.len())
+                        .map(|i| {
+                            let cell = row.get(i).cloned().unwrap_or_default();
+                            if !exported {
+                                return Some(cell);
+                            }
+                            if cell.contains('，') {
+                                full_width_commas += 1;
+                            }
+                            Some(cell.replace("\\n", "\n"))
+                        })
+                        .collect()
+                })
+                .collect();
+            let mut warnings = Vec::new();
+            if full_width_commas > 0 {
+                warnings.push(format!(
+                    "导出的CSV中有{}个单元格包含全角逗号, 导出时半角逗号会转为全角逗号, 导入后保持为全角逗号",
+                    full_width_commas
+                ));
+            }
+            ImportData {
+                columns,
+                rows,
+                warnings,
+            }
+        })
+        .ok_or_else(|| import_error(format!("CSV中没有以主键字段 {} 开头的表头行", key.name)))
+}
+
+fn json_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// JSON为对象数组, 或以主键为键的对象
+fn json_data(content: &str, table: &SheetTable) -> io::Result<ImportData> {
+    let key = &table.columns[0].name;
+    let value: Value = serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| import_error(format!("解析JSON失败: {}", e)))?;
+    let objects: Vec<serde_json::Map<String, Value>> = match value {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::Object(object) => Ok(object),
+                _ => Err(import_error("JSON数组的元素必须是对象".to_string())),
+            })
+            .collect::<io::Result<_>>()?,
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(id, item)| match item {
+                Value::Object(mut object) => {
+                    object
+                        .entry(key.clone())
+                        .or_insert_with(|| Value::String(id));
+                    Ok(object)
+                }
+                _ => Err(import_error(format!("JSON中 {} 的值必须是对象", id))),
+            })
+            .collect::<io::Result<_>>()?,
+        _ => {
+            return Err(import_error(
+                "JSON必须是对象数组或以主键为键的对象".to_string(),
+            ));
+        }
+    };
+
+    let mut columns = vec![key.clone()];
+    for object in &objects {
+        for name in object.keys() {
+            if !columns.contains(name) {
+                columns.push(name.clone());
+            }
+        }
+    }
+    let rows = objects
+        .iter()
+        .map(|object| {
+            columns
+                .iter()
+                .map(|name| object.get(name).map(json_cell))
+                .collect()
+        })
+        .collect();
+    Ok(ImportData {
+        columns,
+        rows,
+        warnings: Vec::new(),
+    })
+}
+
+/// 读取工作表中每个单元格的原始值(不填默认值), 键为 (行号从1开始, 列下标)
+fn read_cells(path: &Path, sheet: &str) -> io::Result<HashMap<(u32, u32), String>> {
+    let mut workbook: Xlsx<_> = open_workbook(path)
+        .map_err(|e| import_error(format!("打开{}失败: {}", path.display(), e)))?;
+    let range = match workbook.worksheet_range(sheet) {
+        Some(Ok(range)) => range,
+        _ => return Err(import_error(format!("读取工作表 {} 失败", sheet))),
+    };
+    let mut cells = HashMap::new();
+    for (row, cells_in_row) in range.rows().enumerate() {
+        for (col, cell) in cells_in_row.iter().enumerate() {
+            let value = cell.to_string();
+            if !value.is_empty() {
+                cells.insert((row as u32 + 1, col as u32), value);
+            }
+        }
+    }
+    Ok(cells)
+}
+
+/// 把CSV或JSON文件按主键合并到Excel目录中同名表所在的工作表: 已有主键的行更新有变化的单元格,
+/// 新主键追加到表尾, 前三行表头不修改; 只能导入会导出的列, 其他列报告警告
+pub fn import_data(excel_dir: &str, file: &Path) -> io::Result<DataImportReport> {
+    let name = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let table = Xlsx2CsvTool::new(excel_dir.to_string(), String::new(), Vec::new())
+        .read_table(&name)?
+        .ok_or_else(|| import_error(format!("Excel目录中没有{}表", name)))?;
+    if table.columns.is_empty() {
+        return Err(import_error(format!("{} 没有导出列", table.source)));
+    }
+    let content = fs::read_to_string(file)?;
+    let is_json = file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let data = if is_json {
+        json_data(&content, &table)?
+    } else {
+        csv_data(&content, &table)?
+    };
+
+    let mut report = DataImportReport {
+        file: file
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        workbook: table.workbook.clone(),
+        sheet: table.sheet_name.clone(),
+        updated: Vec::new(),
+        added: Vec::new(),
+        unchanged: 0,
+        warnings: data.warnings,
+    };
+
+    // 导入列 -> 工作表列
+    let columns: Vec<Option<(u32, ValueKind)>> = data
+        .columns
+        .iter()
+        .map(|name| {
+            let column = table.columns.iter().find(|c| &c.name == name);
+            if column.is_none() {
+                report.warnings.push(format!("{} 不是导出列, 已忽略", name));
+            }
+            column.map(|c| (c.cell_index as u32, c.column_type.kind()))
+        })
+        .collect();
+    let key_col = table.columns[0].cell_index as u32;
+
+    let path = Path::new(excel_dir).join(&table.workbook);
+    let cells = read_cells(&path, &table.sheet_name)?;
+    let mut last_row = cells.keys().map(|&(row, _)| row).max().unwrap_or(3).max(3);
+    let mut key_rows = HashMap::new();
+    for row in 4..=last_row {
+        if let Some(key) = cells.get(&(row, key_col)) {
+            key_rows.entry(key.clone()).or_insert(row);
+        }
+    }
+
+    let mut updates: BTreeMap<u32, BTreeMap<u32, CellValue>> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for values in &data.rows {
+        let key = values[0].clone().unwrap_or_default();
+        if key.trim().is_empty() {
+            report.warnings.push("主键为空的行已忽略".to_string());
+            continue;
+        }
+        if !seen.insert(key.clone()) {
+            report
+                .warnings
+                .push(format!("主键 {} 重复, 只导入第一行", key));
+            continue;
+        }
+        let existing_row = key_rows.get(&key).copied();
+        let is_new = existing_row.is_none();
+        let row = existing_row.unwrap_or_else(|| {
+            last_row += 1;
+            report.added.push(key.clone());
+            last_row
+        });
+        let mut changed = Vec::new();
+        for (value, column) in values.iter().zip(&columns) {
+            let (Some(value), Some((col, kind))) = (value, column) else {
+                continue;
+            };
+            let old = cells.get(&(row, *col)).cloned().unwrap_or_default();
+            if old == *value {
+                continue;
+            }
+            // NaN和inf写入数值单元格后Excel会认为文件损坏, 按字符串写入
+            let numeric = matches!(kind, ValueKind::Int | ValueKind::Float)
+                && value
+                    .trim()
+                    .parse::<f64>()
+                    .is_ok_and(|number| number.is_finite());
+            updates.entry(row).or_default().insert(
+                *col,
+                CellValue {
+                    text: value.clone(),
+                    numeric,
+                },
+            );
+            if !is_new {
+                let column = table.columns.iter().find(|c| c.cell_index as u32 == *col);
+                changed.push((
+                    column.map(|c| c.name.clone()).unwrap_or_default(),
+                    old,
+                    value.clone(),
+                ));
+            }
+        }
+        if is_new {
+            continue;
+        }
+        if changed.is_empty() {
+            report.unchanged += 1;
+        } else {
+            report.updated.push(RowChange {
+                key,
+                cells: changed,
+            });
+        }
+    }
+
+    if table.meta.line_limit > 0 && last_row as usize > table.meta.line_limit {
+        report.warnings.push(format!(
+            "表的行数限制为{}, 超出的行不会导出",
+            table.meta.line_limit
+        ));
+    }
+    if !updates.is_empty() {
+        write_cells(&path, &table.sheet_name, &updates, &mut report.warnings)?;
+    }
+    Ok(report)
+}
+
+fn zip_error(e: zip::result::ZipError) -> Error {
+    import_error(format!("读写工作簿失败: {}", e))
+}
+
+fn xml_error(e: impl std::fmt::Display) -> Error {
+    import_error(format!("解析工作簿XML失败: {}", e))
+}
+
+fn read_entry<R: Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> io::Result<String> {
+    let mut content = String::new();
+    archive
+        .by_name(name)
+        .map_err(zip_error)?
+        .read_to_string(&mut content)?;
+    Ok(content)
+}
+
+fn attribute(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|attr| attr.key.as_ref() == name)
+        .and_then(|attr| attr.unescape_value().ok())
+        .map(|value| value.to_string())
+}
+
+/// 按 xl/workbook.xml 和它的关系文件找到工作表XML在压缩包中的路径
+fn sheet_entry<R: Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    sheet: &str,
+) -> io::Result<String> {
+    let workbook = read_entry(archive, WORKBOOK_ENTRY)?;
+    let mut reader = XmlReader::from_str(&workbook);
+    let mut relation = None;
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"sheet"
+                    && attribute(&e, b"name").as_deref() == Some(sheet) =>
+            {
+                relation = e
+                    .attributes()
+                    .flatten()
+                    .find(|attr| {
+                        attr.key.local_name().as_ref() == b"id" && attr.key.prefix().is_some()
+                    })
+                    .and_then(|attr| attr.unescape_value().ok())
+                    .map(|value| value.to_string());
+                break;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let relation = relation.ok_or_else(|| import_error(format!("工作簿中没有工作表 {}", sheet)))?;
+
+    let relations = read_entry(archive, "xl/_rels/workbook.xml.rels")?;
+    let mut reader = XmlReader::from_str(&relations);
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"Relationship"
+                    && attribute(&e, b"Id").as_deref() == Some(relation.as_str()) =>
+            {
+                let target = attribute(&e, b"Target").unwrap_or_default();
+                return Ok(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                });
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Err(import_error(format!("找不到工作表 {} 的XML", sheet)))
+}
+
+/// 工作簿XML在压缩包中的路径
+const WORKBOOK_ENTRY: &str = "xl/workbook.xml";
+
+/// 单元格坐标 "AB12" -> (列下标, 行号)
+fn parse_cell_ref(reference: &str) -> Option<(u32, u32)> {
+    let digits = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(digits);
+    if letters.is_empty() {
+        return None;
+    }
+    let mut col = 0u32;
+    for c in letters.chars() {
+        if !c.is_ascii_uppercase() {
+            return None;
+        }
+        col = col * 26 + (c as u32 - 'A' as u32 + 1);
+    }
+    Some((col - 1, digits.parse().ok()?))
+}
+
+fn cell_xml(prefix: &str, row: u32, col: u32, style: Option<&str>, value: &CellValue) -> String {
+    let reference = cell_name(col as usize, row as usize);
+    let style = style.map(|s| format!(" s=\"{}\"", s)).unwrap_or_default();
+    if value.text.is_empty() {
+        format!("<{p}c r=\"{}\"{}/>", reference, style, p = prefix)
+    } else if value.numeric {
+        let number: f64 = value.text.trim().parse().unwrap_or_default();
+        format!(
+            "<{p}c r=\"{}\"{}><{p}v>{}</{p}v></{p}c>",
+            reference,
+            style,
+            number,
+            p = prefix
+        )
+    } else {
+        format!(
+            "<{p}c r=\"{}\"{} t=\"inlineStr\"><{p}is><{p}t xml:space=\"preserve\">{}</{p}t></{p}is></{p}c>",
+            reference,
+            style,
+            escape(value.text.as_str()),
+            p = prefix
+        )
+    }
+}
+
+/// 行中已有的单元格
+struct ExistingCell {
+    /// 单元格XML原文
+    xml: Vec<u8>,
+    style: Option<String>,
+    /// 包含公式(含共享公式), 覆盖后 calcChain 和引用它的共享公式会失效
+    formula: bool,
+}
+
+/// 一行的单元格: 列下标 -> 单元格
+type RowCells = BTreeMap<u32, ExistingCell>;
+
+/// 读取一行中已有的单元格
+fn read_row_cells(reader: &mut XmlReader<&[u8]>) -> io::Result<RowCells> {
+    let mut cells = BTreeMap::new();
+    let mut next_col = 0;
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        let (start, empty) = match &event {
+            Event::Start(e) if e.local_name().as_ref() == b"c" => (e.clone().into_owned(), false),
+            Event::Empty(e) if e.local_name().as_ref() == b"c" => (e.clone().into_owned(), true),
+            Event::End(e) if e.local_name().as_ref() == b"row" => return Ok(cells),
+            Event::Eof => return Err(xml_error("行没有结束")),
+            _ => continue,
+        };
+        let col = attribute(&start, b"r")
+            .and_then(|r| parse_cell_ref(&r))
+            .map(|(col, _)| col)
+            .unwrap_or(next_col);
+        next_col = col + 1;
+        let style = attribute(&start, b"s");
+        let mut formula = false;
+        let mut writer = Writer::new(Vec::new());
+        writer.write_event(event).map_err(xml_error)?;
+        if !empty {
+            loop {
+                let event = reader.read_event().map_err(xml_error)?;
+                let end = matches!(&event, Event::End(e) if e.local_name().as_ref() == b"c");
+                match &event {
+                    Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"f" => {
+                        formula = true
+                    }
+                    Event::Eof => return Err(xml_error("单元格没有结束")),
+                    _ => {}
+                }
+                writer.write_event(event).map_err(xml_error)?;
+                if end {
+                    break;
+                }
+            }
+        }
+        cells.insert(
+            col,
+            ExistingCell {
+                xml: writer.into_inner(),
+                style,
+                formula,
+            },
+        );
+    }
+}
+
+/// 修改工作表XML: 有更新的行替换对应单元格并保留样式, 新行按行号插入 sheetData, 同时更新 dimension;
+/// 要修改的单元格是公式时报错, 不修改工作簿
+fn patch_sheet_xml(
+    xml: &str,
+    updates: &BTreeMap<u32, BTreeMap<u32, CellValue>>,
+) -> io::Result<String> {
+    let mut reader = XmlReader::from_str(xml);
+    let mut writer = Writer::new(Vec::new());
+    let mut pending: BTreeMap<u32, &BTreeMap<u32, CellValue>> =
+        updates.iter().map(|(&row, cells)| (row, cells)).collect();
+    let mut prefix = String::new();
+    let mut last_row = 0;
+    let max_row = updates.keys().copied().max().unwrap_or(0);
+    let max_col = updates
+        .values()
+        .flat_map(|cells| cells.keys().copied())
+        .max()
+        .unwrap_or(0);
+
+    let write_row = |writer: &mut Writer<Vec<u8>>,
+                     prefix: &str,
+                     start: &BytesStart,
+                     row: u32,
+                     mut existing: RowCells,
+                     cells: &BTreeMap<u32, CellValue>|
+     -> io::Result<()> {
+        for (&col, value) in cells {
+            let style = match existing.get(&col) {
+                Some(cell) if cell.formula => {
+                    return Err(import_error(format!(
+                        "单元格 {} 是公式, 不能导入覆盖",
+                        cell_name(col as usize, row as usize)
+                    )));
+                }
+                Some(cell) => cell.style.clone(),
+                None => None,
+            };
+            let xml = cell_xml(prefix, row, col, style.as_deref(), value);
+            existing.insert(
+                col,
+                ExistingCell {
+                    xml: xml.into_bytes(),
+                    style,
+                    formula: false,
+                },
+            );
+        }
+        // 单元格数变化后 spans 可能不准确, 去掉由Excel重新计算
+        let name = String::from_utf8_lossy(start.name().as_ref()).to_string();
+        let mut row_start = BytesStart::new(name.clone());
+        row_start.extend_attributes(
+            start
+                .attributes()
+                .flatten()
+                .filter(|attr| attr.key.as_ref() != b"spans"),
+        );
+        if attribute(&row_start, b"r").is_none() {
+            row_start.push_attribute(("r", row.to_string().as_str()));
+        }
+        writer
+            .write_event(Event::Start(row_start))
+            .map_err(xml_error)?;
+        for cell in existing.values() {
+            writer.get_mut().extend_from_slice(&cell.xml);
+        }
+        writer
+            .write_event(Event::End(quick_xml::events::BytesEnd::new(name)))
+            .map_err(xml_error)
+    };
+
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"sheetData" => {
+                prefix = prefix_of(e);
+                if matches!(event, Event::Empty(_)) {
+                    let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                    let start = e.clone().into_owned();
+                    writer.write_event(Event::Start(start)).map_err(xml_error)?;
+                    for (row, cells) in std::mem::take(&mut pending) {
+                        let start = BytesStart::new(format!("{}row", prefix));
+                        write_row(&mut writer, &prefix, &start, row, BTreeMap::new(), cells)?;
+                    }
+                    writer
+                        .write_event(Event::End(quick_xml::events::BytesEnd::new(name)))
+                        .map_err(xml_error)?;
+                    continue;
+                }
+            }
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"row" => {
+                let row = attribute(e, b"r")
+                    .and_then(|r| r.parse().ok())
+                    .unwrap_or(last_row + 1);
+                // 新行插在第一个行号更大的已有行之前, 保持行号递增
+                let rest = pending.split_off(&row);
+                for (r, cells) in std::mem::replace(&mut pending, rest) {
+                    let start = BytesStart::new(format!("{}row", prefix));
+                    write_row(&mut writer, &prefix, &start, r, BTreeMap::new(), cells)?;
+                }
+                last_row = row;
+                if let Some(cells) = pending.remove(&row) {
+                    let start = e.clone().into_owned();
+                    let existing = if matches!(event, Event::Start(_)) {
+                        read_row_cells(&mut reader)?
+                    } else {
+                        BTreeMap::new()
+                    };
+                    write_row(&mut writer, &prefix, &start, row, existing, cells)?;
+                    continue;
+                }
+            }
+            Event::End(e) if e.local_name().as_ref() == b"sheetData" => {
+                for (row, cells) in std::mem::take(&mut pending) {
+                    let start = BytesStart::new(format!("{}row", prefix));
+                    write_row(&mut writer, &prefix, &start, row, BTreeMap::new(), cells)?;
+                }
+            }
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"dimension" => {
+                let reference = attribute(e, b"ref").unwrap_or_default();
+                let (first, last) = reference
+                    .split_once(':')
+                    .unwrap_or((&reference, &reference));
+                let (last_col, last_line) = parse_cell_ref(last).unwrap_or((0, 1));
+                let last = cell_name(
+                    last_col.max(max_col) as usize,
+                    last_line.max(max_row) as usize,
+                );
+                let first = if first.is_empty() { "A1" } else { first };
+                let mut dimension = BytesStart::new(format!("{}dimension", prefix_of(e)));
+                dimension.push_attribute(("ref", format!("{}:{}", first, last).as_str()));
+                writer
+                    .write_event(Event::Empty(dimension))
+                    .map_err(xml_error)?;
+                if matches!(event, Event::Start(_)) {
+                    reader.read_to_end(e.name()).map_err(xml_error)?;
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        writer.write_event(event).map_err(xml_error)?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(xml_error)
+}
+
+/// 工作簿XML中 calcPr 之后的元素, 没有 calcPr 时插入到第一个这样的元素之前
+const AFTER_CALC_PR: &[&[u8]] = &[
+    b"oleSize",
+    b"customWorkbookViews",
+    b"pivotCaches",
+    b"smartTagPr",
+    b"smartTagTypes",
+    b"webPublishing",
+    b"fileRecoveryPr",
+    b"webPublishObjects",
+    b"extLst",
+];
+
+/// 修改工作簿XML: 设置 calcPr 的 fullCalcOnLoad, Excel打开时重新计算所有公式
+fn patch_workbook_xml(xml: &str) -> io::Result<String> {
+    let mut reader = XmlReader::from_str(xml);
+    let mut writer = Writer::new(Vec::new());
+    let mut prefix = String::new();
+    let mut depth = 0;
+    let mut done = false;
+    loop {
+        let event = reader.read_event().map_err(xml_error)?;
+        match &event {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"workbook" => {
+                prefix = prefix_of(e);
+            }
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"calcPr" => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                let mut calc = BytesStart::new(name);
+                calc.extend_attributes(
+                    e.attributes()
+                        .flatten()
+                        .filter(|attr| attr.key.as_ref() != b"fullCalcOnLoad"),
+                );
+                calc.push_attribute(("fullCalcOnLoad", "1"));
+                let calc = match event {
+                    Event::Start(_) => Event::Start(calc),
+                    _ => Event::Empty(calc),
+                };
+                if matches!(calc, Event::Start(_)) {
+                    depth += 1;
+                }
+                writer.write_event(calc).map_err(xml_error)?;
+                done = true;
+                continue;
+            }
+            Event::Start(e) | Event::Empty(e)
+                if !done && depth == 1 && AFTER_CALC_PR.contains(&e.local_name().as_ref()) =>
+            {
+                write_calc_pr(&mut writer, &prefix)?;
+                done = true;
+            }
+            Event::End(e) if !done && depth == 1 && e.local_name().as_ref() == b"workbook" => {
+                write_calc_pr(&mut writer, &prefix)?;
+                done = true;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        match &event {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            _ => {}
+        }
+        writer.write_event(event).map_err(xml_error)?;
+    }
+    String::from_utf8(writer.into_inner()).map_err(xml_error)
+}
+
+fn write_calc_pr(writer: &mut Writer<Vec<u8>>, prefix: &str) -> io::Result<()> {
+    let mut calc = BytesStart::new(format!("{}calcPr", prefix));
+    calc.push_attribute(("fullCalcOnLoad", "1"));
+    writer.write_event(Event::Empty(calc)).map_err(xml_error)
+}
+
+/// 工作表XML中是否有公式
+fn has_formulas(xml: &str) -> io::Result<bool> {
+    let mut reader = XmlReader::from_str(xml);
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"f" => {
+                return Ok(true);
+            }
+            Event::Eof => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+fn prefix_of(e: &BytesStart) -> String {
+    e.name()
+        .prefix()
+        .map(|p| format!("{}:", String::from_utf8_lossy(p.as_ref())))
+        .unwrap_or_default()
+}
+
+/// 把修改写回工作簿: 替换工作表XML, 并在工作簿XML中设置打开时重新计算公式, 其他文件原样复制,
+/// 先写临时文件再替换原文件; 公式的缓存值要在Excel中保存后才会更新, 工作表有公式时警告
+fn write_cells(
+    path: &Path,
+    sheet: &str,
+    updates: &BTreeMap<u32, BTreeMap<u32, CellValue>>,
+    warnings: &mut Vec<String>,
+) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path)?).map_err(zip_error)?;
+    let entry = sheet_entry(&mut archive, sheet)?;
+    let sheet_xml = read_entry(&mut archive, &entry)?;
+    if has_formulas(&sheet_xml)? {
+        warnings.push(
+            "工作表包含公式, 公式的值在Excel中打开并保存后才会更新, 导出前请先保存工作簿"
+                .to_string(),
+        );
+    }
+    let xml = patch_sheet_xml(&sheet_xml, updates)?;
+    let workbook_xml = patch_workbook_xml(&read_entry(&mut archive, WORKBOOK_ENTRY)?)?;
+
+    let file_name = path
+        .file_name()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = path.with_file_name(format!("~{}.import", file_name));
+    let result = (|| -> io::Result<()> {
+        let mut writer = zip::ZipWriter::new(fs::File::create(&temp)?);
+        for i in 0..archive.len() {
+            let file = archive.by_index(i).map_err(zip_error)?;
+            let content = if file.name() == entry {
+                &xml
+            } else if file.name() == WORKBOOK_ENTRY {
+                &workbook_xml
+            } else {
+                writer.raw_copy_file(file).map_err(zip_error)?;
+                continue;
+            };
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            let name = file.name().to_string();
+            writer.start_file(name, options).map_err(zip_error)?;
+            writer.write_all(content.as_bytes())?;
+        }
+        writer.finish().map_err(zip_error)?;
+        Ok(())
+    })();
+    if let Err(err) = result {
+        let _ = fs::remove_file(&temp);
+        return Err(err);
+    }
+    fs::rename(&temp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temp);
+    })
+}
+
+/// 导入结果
+pub fn data_import_report_ui(reports: &[DataImportReport], ui: &mut egui::Ui) {
+    for (i, report) in reports.iter().enumerate() {
+        let summary = format!(
+            "{} -> {} - {}: 修改{}行, 新增{}行, 未变化{}行, 警告{}条",
+            report.file,
+            report.workbook,
+            report.sheet,
+            report.updated.len(),
+            report.added.len(),
+            report.unchanged,
+            report.warnings.len()
+        );
+        egui::CollapsingHeader::new(summary)
+            .id_salt(("data_import_report", i))
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical()
+                    .id_salt(("data_import_scroll", i))
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for warning in &report.warnings {
+                            ui.colored_label(egui::Color32::YELLOW, warning);
+                        }
+                        for key in &report.added {
+                            ui.label(format!("+ 行 {}", key));
+                        }
+                        for row in &report.updated {
+                            let cells: Vec<String> = row
+                                .cells
+                                .iter()
+                                .map(|(column, old, new)| {
+                                    format!("{}: {:?} -> {:?}", column, old, new)
+                                })
+                                .collect();
+                            ui.label(format!("~ 行 {}: {}", row.key, cells.join("; ")));
+                        }
+                    });
+            });
+    }
+}
+
+pub fn data_import_ui(app: &mut crate::App, ui: &mut egui::Ui) {
+    ui.label("导入数据");
+    ui.add_space(5.0);
+
+    ui.horizontal(|ui| {
+        if ui
+            .add(egui::Button::new("导入数据到工作簿").min_size(egui::vec2(150.0, 30.0)))
+            .clicked()
+        {
+            let Some(files) = rfd::FileDialog::new()
+                .add_filter("CSV / JSON", &["csv", "json"])
+                .pick_files()
+            else {
+                return;
+            };
+            let mut reports = Vec::new();
+            for file in files {
+                match import_data(&app.excel_dir, &file) {
+                    Ok(report) => {
+                        app.toasts
+                            .success(format!(
+                                "{}: 修改{}行, 新增{}行",
+                                report.file,
+                                report.updated.len(),
+                                report.added.len()
+                            ))
+                            .duration(Duration::from_secs(5).into());
+                        reports.push(report);
+                    }
+                    Err(err) => {
+                        app.toasts
+                            .error(format!("导入{}失败: {}", file.display(), err))
+                            .duration(Duration::from_secs(5).into());
+                    }
+                }
+            }
+            app.data_import = reports;
+        }
+    });
+
+    data_import_report_ui(&app.data_import, ui);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET_XML: &str = concat!(
+        r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+        r#"<dimension ref="A1:B3"/><sheetData>"#,
+        r#"<row r="1" spans="1:2"><c r="A1" s="1"><v>1</v></c><c r="B1" s="2" t="s"><v>0</v></c></row>"#,
+        r#"<row r="3" spans="1:2"><c r="A3"><v>3</v></c><c r="B3"><f>A3*2</f><v>6</v></c></row>"#,
+        r#"</sheetData></worksheet>"#
+    );
+
+    fn updates(cells: &[(u32, u32, &str, bool)]) -> BTreeMap<u32, BTreeMap<u32, CellValue>> {
+        let mut updates: BTreeMap<u32, BTreeMap<u32, CellValue>> = BTreeMap::new();
+        for &(row, col, text, numeric) in cells {
+            updates.entry(row).or_default().insert(
+                col,
+                CellValue {
+                    text: text.to_string(),
+                    numeric,
+                },
+            );
+        }
+        updates
+    }
+
+    #[test]
+    fn cell_refs() {
+        assert_eq!(parse_cell_ref("A1"), Some((0, 1)));
+        assert_eq!(parse_cell_ref("Z10"), Some((25, 10)));
+        assert_eq!(parse_cell_ref("AB12"), Some((27, 12)));
+        assert_eq!(parse_cell_ref("12"), None);
+        assert_eq!(parse_cell_ref("a1"), None);
+        assert_eq!(parse_cell_ref("A"), None);
+    }
+
+    #[test]
+    fn csv_quotes_and_blank_lines() {
+        let rows = parse_csv("\u{feff}id,name\r\n1,\"a,b\"\n\n2,\"line1\nline2 \"\"x\"\"\"\n,\n3,");
+        assert_eq!(
+            rows,
+            vec![
+                vec!["id", "name"],
+                vec!["1", "a,b"],
+                vec!["2", "line1\nline2 \"x\""],
+                vec!["3", ""],
+            ]
+        );
+    }
+
+    fn item_table() -> SheetTable {
+        let column = |name: &str, column_type: &str, cell_index| crate::xlsx2csv::SheetColumn {
+            comment: name.to_string(),
+            name: name.to_string(),
+            column_type: crate::column_type::ColumnType::parse(column_type),
+            cell_index,
+        };
+        SheetTable {
+            meta: crate::table_meta::TableMeta::parse("Item").0,
+            source: "Item/Sheet1".to_string(),
+            workbook: "Item.xlsx".to_string(),
+            sheet_name: "Sheet1".to_string(),
+            output_dir: Default::default(),
+            columns: vec![column("id", "int", 0), column("text", "string", 1)],
+            rows: Vec::new(),
+            filtered_rows: 0,
+        }
+    }
+
+    #[test]
+    fn exported_csv_is_unescaped() {
+        let data = csv_data(
+            "Item,说明\nid,text\nint,string\n1,第一行\\n第二行\n2,\"a，b\"\n",
+            &item_table(),
+        )
+        .unwrap();
+        assert_eq!(data.columns, ["id", "text"]);
+        assert_eq!(data.rows[0][1].as_deref(), Some("第一行\n第二行"));
+        assert_eq!(data.rows[1][1].as_deref(), Some("a，b"));
+        assert_eq!(data.warnings.len(), 1);
+    }
+
+    #[test]
+    fn hand_written_csv_is_kept() {
+        let data = csv_data("id,text\n1,\"a\\nb，c\"\n", &item_table()).unwrap();
+        assert_eq!(data.rows[0][1].as_deref(), Some("a\\nb，c"));
+        assert!(data.warnings.is_empty());
+    }
+
+    #[test]
+    fn updates_cell_and_keeps_style() {
+        let xml = patch_sheet_xml(SHEET_XML, &updates(&[(1, 1, "新名字", false)])).unwrap();
+        assert!(xml.contains(r#"<c r="A1" s="1"><v>1</v></c>"#));
+        assert!(xml.contains(
+            r#"<c r="B1" s="2" t="inlineStr"><is><t xml:space="preserve">新名字</t></is></c>"#
+        ));
+        // 修改的行去掉 spans, 其他行原样保留
+        assert!(xml.contains(r#"<row r="1"><c r="A1""#));
+        assert!(xml.contains(r#"<row r="3" spans="1:2">"#));
+    }
+
+    #[test]
+    fn inserts_row_between_existing_rows() {
+        let xml = patch_sheet_xml(SHEET_XML, &updates(&[(2, 0, "2", true)])).unwrap();
+        let row1 = xml.find(r#"<row r="1""#).unwrap();
+        let row2 = xml
+            .find(r#"<row r="2"><c r="A2"><v>2</v></c></row>"#)
+            .unwrap();
+        let row3 = xml.find(r#"<row r="3""#).unwrap();
+        assert!(row1 < row2 && row2 < row3);
+        assert!(xml.contains(r#"<dimension ref="A1:B3"/>"#));
+    }
+
+    #[test]
+    fn appends_row_and_updates_dimension() {
+        let xml = patch_sheet_xml(SHEET_XML, &updates(&[(5, 2, "x", false)])).unwrap();
+        let row3 = xml.find(r#"<row r="3""#).unwrap();
+        let row5 = xml.find(r#"<row r="5"><c r="C5" t="inlineStr">"#).unwrap();
+        assert!(row3 < row5);
+        assert!(xml.find("</sheetData>").unwrap() > row5);
+        assert!(xml.contains(r#"<dimension ref="A1:C5"/>"#));
+    }
+
+    #[test]
+    fn fills_empty_sheet_data() {
+        let xml = concat!(
+            r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">"#,
+            r#"<dimension ref="A1"/><sheetData/></worksheet>"#
+        );
+        let xml = patch_sheet_xml(xml, &updates(&[(1, 0, "1", true), (2, 1, "b", false)])).unwrap();
+        assert!(xml.contains(concat!(
+            r#"<sheetData><row r="1"><c r="A1"><v>1</v></c></row>"#,
+            r#"<row r="2"><c r="B2" t="inlineStr"><is><t xml:space="preserve">b</t></is></c></row>"#,
+            r#"</sheetData>"#
+        )));
+        assert!(xml.contains(r#"<dimension ref="A1:B2"/>"#));
+    }
+
+    #[test]
+    fn sets_full_calc_on_load() {
+        let workbook = |inner: &str| {
+            format!(
+                r#"<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheets><sheet name="S" sheetId="1"/></sheets>{}</workbook>"#,
+                inner
+            )
+        };
+        let xml = patch_workbook_xml(&workbook(r#"<calcPr calcId="191029" fullCalcOnLoad="0"/>"#))
+            .unwrap();
+        assert!(xml.contains(r#"<calcPr calcId="191029" fullCalcOnLoad="1"/>"#));
+
+        let xml = patch_workbook_xml(&workbook(
+            r#"<definedNames/><extLst><ext uri="x"/></extLst>"#,
+        ))
+        .unwrap();
+        assert!(xml.contains(r#"<definedNames/><calcPr fullCalcOnLoad="1"/><extLst>"#));
+
+        let xml = patch_workbook_xml(&workbook("")).unwrap();
+        assert!(xml.ends_with(r#"</sheets><calcPr fullCalcOnLoad="1"/></workbook>"#));
+
+        let xml = patch_workbook_xml(
+            r#"<x:workbook xmlns:x="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><x:sheets/></x:workbook>"#,
+        )
+        .unwrap();
+        assert!(xml.contains(r#"<x:sheets/><x:calcPr fullCalcOnLoad="1"/></x:workbook>"#));
+    }
+
+    #[test]
+    fn detects_formulas() {
+        assert!(has_formulas(SHEET_XML).unwrap());
+        let xml = patch_sheet_xml(SHEET_XML, &updates(&[(3, 0, "4", true)])).unwrap();
+        assert!(has_formulas(&xml).unwrap());
+        assert!(!has_formulas(r#"<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData></worksheet>"#).unwrap());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_formula() {
+        let result = patch_sheet_xml(SHEET_XML, &updates(&[(3, 1, "7", true)]));
+        assert!(result.unwrap_err().to_string().contains("B3"));
+        // 同一行的普通单元格可以修改
+        let xml = patch_sheet_xml(SHEET_XML, &updates(&[(3, 0, "4", true)])).unwrap();
+        assert!(xml.contains(r#"<c r="A3"><v>4</v></c><c r="B3"><f>A3*2</f><v>6</v></c>"#));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::file_utils::read_json_or_default;
+use crate::xlsx2csv::SheetTable;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::{fs, io, path};
+
+/// 输出目录下保存上次导出的所有表数据, 用于和本次导出对比
+pub const EXPORT_SNAPSHOT_FILE: &str = ".export_snapshot.json";
+/// 输出目录下保存本次导出与上次导出的差异, 每次导出覆盖
+pub const EXPORT_DIFF_FILE: &str = "export_diff.txt";
+
+/// 表的输出路径(相对目标目录, 不带扩展名) -> 表数据
+pub type ExportSnapshot = BTreeMap<String, TableSnapshot>;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TableSnapshot {
+    pub source: String,
+    pub columns: Vec<String>,
+    /// 主键 -> 整行的值, 与 columns 一一对应
+    pub rows: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TableStatus {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Clone)]
+pub struct TableDiff {
+    pub table: String,
+    pub source: String,
+    pub status: TableStatus,
+    pub added_columns: Vec<String>,
+    pub removed_columns: Vec<String>,
+    pub added_rows: Vec<String>,
+    pub removed_rows: Vec<String>,
+    pub changed_rows: Vec<RowChange>,
+}
+
+#[derive(Clone)]
+pub struct RowChange {
+    pub key: String,
+    /// 列名, 旧值, 新值
+    pub cells: Vec<(String, String, String)>,
+}
+
+/// 表在快照中的路径, 与导出文件的相对路径一致
+pub fn table_id(table: &SheetTable) -> String {
+    table
+        .output_dir
+        .join(&table.meta.name)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+impl TableSnapshot {
+    pub fn from_table(table: &SheetTable) -> Self {
+        Self {
+            source: table.source.clone(),
+            columns: table.columns.iter().map(|c| c.name.clone()).collect(),
+            rows: table
+                .rows
+                .iter()
+                .map(|row| (row.values[0].clone(), row.values.clone()))
+                .collect(),
+        }
+    }
+}
+
+pub fn load_snapshot(output_dir: &path::Path) -> ExportSnapshot {
+    read_json_or_default(output_dir.join(EXPORT_SNAPSHOT_FILE))
+}
+
+pub fn save_snapshot(output_dir: &path::Path, snapshot: &ExportSnapshot) -> io::Result<()> {
+    fs::write(
+        output_dir.join(EXPORT_SNAPSHOT_FILE),
+        serde_json::to_string(snapshot)?,
+    )
+}
+
+/// 按主键对比两次导出, 只返回有变化的表
+pub fn diff_snapshots(previous: &ExportSnapshot, current: &ExportSnapshot) -> Vec<TableDiff> {
+    let tables: BTreeSet<&String> = previous.keys().chain(current.keys()).collect();
+    let mut diffs = Vec::new();
+    for table in tables {
+        let diff = match (previous.get(table), current.get(table)) {
+            (None, Some(new)) => TableDiff {
+                table: table.clone(),
+                source: new.source.clone(),
+                status: TableStatus::Added,
+                added_columns: new.columns.clone(),
+                removed_columns: Vec::new(),
+                added_rows: new.rows.keys().cloned().collect(),
+                removed_rows: Vec::new(),
+                changed_rows: Vec::new(),
+            },
+            (Some(old), None) => TableDiff {
+                table: table.clone(),
+                source: old.source.clone(),
+                status: TableStatus::Removed,
+                added_columns: Vec::new(),
+                removed_columns: old.columns.clone(),
+                added_rows: Vec::new(),
+                removed_rows: old.rows.keys().cloned().collect(),
+                changed_rows: Vec::new(),
+            },
+            (Some(old), Some(new)) => match diff_table(table, old, new) {
+                Some(diff) => diff,
+                None => continue,
+            },
+            (None, None) => continue,
+        };
+        diffs.push(diff);
+    }
+    diffs
+}
+
+fn diff_table(table: &str, old: &TableSnapshot, new: &TableSnapshot) -> Option<TableDiff> {
+    let added_columns: Vec<String> = new
+        .columns
+        .iter()
+        .filter(|c| !old.columns.contains(c))
+        .cloned()
+        .collect();
+    let removed_columns: Vec<String> = old
+        .columns
+        .iter()
+        .filter(|c| !new.columns.contains(c))
+        .cloned()
+        .collect();
+    // 两次都存在的列: 列名, 旧下标, 新下标
+    let common_columns: Vec<(&String, usize, usize)> = new
+        .columns
+        .iter()
+        .enumerate()
+        .filter_map(|(new_index, name)| {
+            let old_index = old.columns.iter().position(|c| c == name)?;
+            Some((name, old_index, new_index))
+        })
+        .collect();
+
+    let added_rows = new
+        .rows
+        .keys()
+        .filter(|key| !old.rows.contains_key(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    let removed_rows = old
+        .rows
+        .keys()
+        .filter(|key| !new.rows.contains_key(*key))
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut changed_rows = Vec::new();
+    for (key, new_values) in &new.rows {
+        let Some(old_values) = old.rows.get(key) else {
+            continue;
+        };
+        let cells: Vec<(String, String, String)> = common_columns
+            .iter()
+            .filter(|(_, old_index, new_index)| old_values[*old_index] != new_values[*new_index])
+            .map(|&(name, old_index, new_index)| {
+                (
+                    name.clone(),
+                    old_values[old_index].clone(),
+                    new_values[new_index].clone(),
+                )
+            })
+            .collect();
+        if !cells.is_empty() {
+            changed_rows.push(RowChange {
+                key: key.clone(),
+                cells,
+            });
+        }
+    }
+
+    let unchanged = added_columns.is_empty()
+        && removed_columns.is_empty()
+        && added_rows.is_empty()
+        && removed_rows.is_empty()
+        && changed_rows.is_empty();
+    if unchanged {
+        return None;
+    }
+    Some(TableDiff {
+        table: table.to_string(),
+        source: new.source.clone(),
+        status: TableStatus::Changed,
+        added_columns,
+        removed_columns,
+        added_rows,
+        removed_rows,
+        changed_rows,
+    })
+}
+
+impl TableDiff {
+    pub fn summary(&self) -> String {
+        match self.status {
+            TableStatus::Added => format!(
+                "新表 {} ({}): {}行",
+                self.table,
+                self.source,
+                self.added_rows.len()
+            ),
+            TableStatus::Removed => format!(
+                "删除表 {} ({}): {}行",
+                self.table,
+                self.source,
+                self.removed_rows.len()
+            ),
+            TableStatus::Changed => format!(
+                "{} ({}): 新增{}行, 删除{}行, 修改{}行, 新增{}列, 删除{}列",
+                self.table,
+                self.source,
+                self.added_rows.len(),
+                self.removed_rows.len(),
+                self.changed_rows.len(),
+                self.added_columns.len(),
+                self.removed_columns.len()
+            ),
+        }
+    }
+
+    /// 变化明细, 新表和删除的表只有汇总
+    pub fn details(&self) -> Vec<String> {
+        if self.status != TableStatus::Changed {
+            return Vec::new();
+        }
+        let mut lines = Vec::new();
+        lines.extend(self.added_columns.iter().map(|c| format!("+ 列 {}", c)));
+        lines.extend(self.removed_columns.iter().map(|c| format!("- 列 {}", c)));
+        lines.extend(self.added_rows.iter().map(|key| format!("+ 行 {}", key)));
+        lines.extend(self.removed_rows.iter().map(|key| format!("- 行 {}", key)));
+        for row in &self.changed_rows {
+            let cells: Vec<String> = row
+                .cells
+                .iter()
+                .map(|(column, old, new)| format!("{}: {:?} -> {:?}", column, old, new))
+                .collect();
+            lines.push(format!("~ 行 {}: {}", row.key, cells.join("; ")));
+        }
+        lines
+    }
+}
+
+pub fn write_diff_text(diffs: &[TableDiff], path: &path::Path) -> io::Result<()> {
+    let mut content = String::new();
+    if diffs.is_empty() {
+        content.push_str("没有变更\n");
+    }
+    for diff in diffs {
+        let _ = writeln!(content, "{}", diff.summary());
+        for line in diff.details() {
+            let _ = writeln!(content, "  {}", line);
+        }
+    }
+    fs::write(path, content)
+}
+
+pub fn export_diff_ui(diffs: &[TableDiff], ui: &mut egui::Ui) {
+    egui::CollapsingHeader::new(format!("数据变更: {}张表有变更", diffs.len()))
+        .id_salt("export_diff")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("export_diff_scroll")
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for diff in diffs {
+                        let details = diff.details();
+                        if details.is_empty() {
+                            ui.label(diff.summary());
+                            continue;
+                        }
+                        egui::CollapsingHeader::new(diff.summary())
+                            .id_salt(("export_diff_table", &diff.table))
+                            .show(ui, |ui| {
+                                for line in details {
+                                    ui.label(line);
+                                }
+                            });
+                    }
+                });
+        });
+}
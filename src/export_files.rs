@@ -1,7 +1,11 @@
 use crate::AppNotice;
+use crate::export_diff::export_diff_ui;
+use crate::export_report::export_report_ui;
+use crate::file_utils::list_files_recursive;
+use crate::translation::translation_report_ui;
 use crate::xlsx2csv::Xlsx2CsvTool;
 use eframe::egui;
-use std::fs;
+use std::time::Duration;
 
 pub fn export_files_ui(app: &mut crate::App, ui: &mut egui::Ui) {
     ui.heading("导出文件");
@@ -11,19 +15,11 @@ pub fn export_files_ui(app: &mut crate::App, ui: &mut egui::Ui) {
 
     ui.horizontal(|ui| {
         if ui.button("加载文件列表").clicked() && !app.excel_dir.is_empty() {
-            // 从excel_dir读取实际的文件列表
-            if let Ok(entries) = fs::read_dir(&app.excel_dir) {
-                app.files.clear();
-                for entry in entries {
-                    if let Ok(entry) = entry {
-                        if let Some(file_name) = entry.file_name().to_str() {
-                            if file_name.ends_with(".xlsx") {
-                                app.files.push(file_name.to_string());
-                            }
-                        }
-                    }
-                }
-                app.files.sort(); // 按字母顺序排序
+            // 从excel_dir递归读取实际的文件列表, 子目录中的文件显示相对路径
+            if let Ok(files) = list_files_recursive(&app.excel_dir, |path| {
+                path.extension().is_some_and(|ext| ext == "xlsx")
+            }) {
+                app.files = files; // 已按字母顺序排序
                 app.files_loaded = true;
             } else {
                 // 如果目录读取失败，显示错误信息
@@ -52,9 +48,17 @@ pub fn export_files_ui(app: &mut crate::App, ui: &mut egui::Ui) {
             let input_dir = app.excel_dir.clone();
             let output_dir = app.output_dir.clone();
             let selected_files = app.selected_files.clone();
+            let target_version = app.target_version.clone();
+            let mirror_folders = app.mirror_folders;
+            let client_dir = app.client_dir.clone();
+            let fix_unicode = app.fix_unicode;
 
             tokio::spawn(async move {
                 let mut tool = Xlsx2CsvTool::new(input_dir, output_dir, selected_files);
+                tool.set_target_version(target_version);
+                tool.set_mirror_folders(mirror_folders);
+                tool.set_client_dir(client_dir);
+                tool.set_fix_unicode(fix_unicode);
                 let progress_sender = sender.clone();
                 tool.set_progress_callback(move |cur, total, text| {
                     match progress_sender.send(AppNotice::ExportProgress(cur, total, text)) {
@@ -67,9 +71,21 @@ pub fn export_files_ui(app: &mut crate::App, ui: &mut egui::Ui) {
                     }
                 });
                 match tool.exec() {
-                    Ok(_) => sender
-                        .send(AppNotice::Toast(("导出成功".to_string(), 5)))
-                        .unwrap(),
+                    Ok(report) => {
+                        let notice = if report.errors.is_empty() {
+                            AppNotice::Toast((
+                                format!("导出成功, 数据版本{}", report.data_version),
+                                5,
+                            ))
+                        } else {
+                            AppNotice::ToastErr((
+                                format!("导出完成, 但有{}个校验错误", report.errors.len()),
+                                5,
+                            ))
+                        };
+                        sender.send(AppNotice::ExportReport(report)).unwrap();
+                        sender.send(notice).unwrap()
+                    }
                     Err(e) => sender
                         .send(AppNotice::Toast((format!("导出失败:{}", e), 5)))
                         .unwrap(),
@@ -87,6 +103,25 @@ pub fn export_files_ui(app: &mut crate::App, ui: &mut egui::Ui) {
         }
     });
 
+    if let Some(report) = &app.export_report {
+        ui.add_space(10.0);
+        export_report_ui(report, ui);
+        export_diff_ui(&report.diff, ui);
+        match translation_report_ui(&report.translation_issues, ui) {
+            Some(Ok(path)) => {
+                app.toasts
+                    .success(format!("已导出翻译检查报告: {}", path.display()))
+                    .duration(Duration::from_secs(5).into());
+            }
+            Some(Err(e)) => {
+                app.toasts
+                    .error(format!("导出翻译检查报告失败: {}", e))
+                    .duration(Duration::from_secs(5).into());
+            }
+            None => {}
+        }
+    }
+
     ui.add_space(10.0);
     const NUM_COLUMNS: usize = 11; // 每行显示的列数
     // 使用流式布局显示文件列表
@@ -0,0 +1,124 @@
+use crate::export_target::ExportTarget;
+use crate::file_utils::{list_files_recursive, sha256_hex};
+use crate::xlsx2csv::{SheetTable, table_file};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io, path};
+
+/// 每个导出目标目录下的清单文件, 服务器和客户端加载时可据此校验文件并记录数据版本
+pub const MANIFEST_FILE: &str = "manifest.json";
+/// 输出目录下记录的数据版本, 每次导出加1
+const DATA_VERSION_FILE: &str = ".data_version";
+
+#[derive(Serialize)]
+pub struct Manifest {
+    pub data_version: u64,
+    /// 导出时间, Unix时间戳(秒)
+    pub export_time: u64,
+    /// Excel目录所在仓库的版本, 不是git或svn仓库时为空
+    pub excel_revision: String,
+    pub target: String,
+    pub files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize)]
+pub struct ManifestFile {
+    /// 相对导出目标目录的路径
+    pub file: String,
+    /// 表文件对应的表名和行数, 生成器输出的文件没有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// 本次导出的数据版本: 上次的版本加1并写回输出目录
+pub fn next_data_version(output_dir: &path::Path) -> io::Result<u64> {
+    let path = output_dir.join(DATA_VERSION_FILE);
+    let version = fs::read_to_string(&path)
+        .ok()
+        .and_then(|version| version.trim().parse::<u64>().ok())
+        .unwrap_or(0)
+        + 1;
+    fs::create_dir_all(output_dir)?;
+    fs::write(path, version.to_string())?;
+    Ok(version)
+}
+
+/// Excel目录的仓库版本, 依次尝试git和svn
+pub fn excel_revision(excel_dir: &str) -> String {
+    let commands: [&[&str]; 2] = [
+        &["git", "-C", excel_dir, "rev-parse", "HEAD"],
+        &["svn", "info", "--show-item", "revision", excel_dir],
+    ];
+    for command in commands {
+        let output = Command::new(command[0]).args(&command[1..]).output();
+        if let Ok(output) = output {
+            let revision = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if output.status.success() && !revision.is_empty() {
+                return revision;
+            }
+        }
+    }
+    String::new()
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// 列出导出目标目录下的所有文件写入清单, tables 为导出到该目标的表
+pub fn write_manifest(
+    target: &ExportTarget,
+    target_dir: &path::Path,
+    tables: &[&SheetTable],
+    data_version: u64,
+    export_time: u64,
+    excel_revision: &str,
+) -> io::Result<()> {
+    // 导出到同一个文件的表只有第一个被写出
+    let mut table_files: HashMap<String, &SheetTable> = HashMap::new();
+    for table in tables {
+        let file = table_file(table, target)
+            .to_string_lossy()
+            .replace('\\', "/");
+        table_files.entry(file).or_insert(table);
+    }
+
+    let mut files = Vec::new();
+    for file in list_files_recursive(target_dir, |path| {
+        path.file_name().is_none_or(|name| name != MANIFEST_FILE)
+    })? {
+        let data = fs::read(target_dir.join(&file))?;
+        let table = table_files.get(&file);
+        files.push(ManifestFile {
+            table: table.map(|t| t.meta.name.clone()),
+            rows: table.map(|t| t.rows.len()),
+            source: table.map(|t| t.source.clone()),
+            size: data.len() as u64,
+            sha256: sha256_hex(&data),
+            file,
+        });
+    }
+
+    let manifest = Manifest {
+        data_version,
+        export_time,
+        excel_revision: excel_revision.to_string(),
+        target: target.name.clone(),
+        files,
+    };
+    fs::write(
+        target_dir.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+}
@@ -0,0 +1,96 @@
+use crate::export_diff::TableDiff;
+use crate::file_utils::read_json_or_default;
+use crate::translation::TranslationIssue;
+use eframe::egui;
+use std::{fs, io, path};
+
+/// 输出目录下保存上次导出的校验错误, 有错误时不能同步服务器
+pub const EXPORT_ERRORS_FILE: &str = ".export_errors.json";
+
+/// 一次导出的汇总报告, 由 `Xlsx2CsvTool::exec` 生成并在界面上展示
+#[derive(Default, Clone)]
+pub struct ExportReport {
+    /// 本次导出的数据版本, 写入各导出目标的清单
+    pub data_version: u64,
+    pub tables: Vec<TableReport>,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+    pub translation_issues: Vec<TranslationIssue>,
+    /// 与上次导出相比有变化的表
+    pub diff: Vec<TableDiff>,
+}
+
+#[derive(Default, Clone)]
+pub struct TableReport {
+    pub name: String,
+    pub source: String,
+    pub group: String,
+    pub targets: Vec<String>,
+    pub rows: usize,
+    pub filtered_rows: usize,
+}
+
+/// 上次导出的校验错误, 导出未完成时为导出开始时写入的提示
+pub fn last_export_errors(output_dir: &path::Path) -> Vec<String> {
+    read_json_or_default(output_dir.join(EXPORT_ERRORS_FILE))
+}
+
+/// 保存本次导出的校验错误, 没有错误时删除文件
+pub fn save_export_errors(output_dir: &path::Path, errors: &[String]) -> io::Result<()> {
+    let path = output_dir.join(EXPORT_ERRORS_FILE);
+    if errors.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    } else {
+        fs::write(path, serde_json::to_string_pretty(errors)?)
+    }
+}
+
+pub fn export_report_ui(report: &ExportReport, ui: &mut egui::Ui) {
+    let filtered: usize = report.tables.iter().map(|t| t.filtered_rows).sum();
+    egui::CollapsingHeader::new(format!(
+        "导出报告: 数据版本{}, {}张表, 版本过滤{}行, {}个错误, {}条警告",
+        report.data_version,
+        report.tables.len(),
+        filtered,
+        report.errors.len(),
+        report.warnings.len()
+    ))
+    .id_salt("export_report")
+    .show(ui, |ui| {
+        egui::ScrollArea::vertical()
+            .id_salt("export_report_scroll")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for error in &report.errors {
+                    ui.colored_label(egui::Color32::RED, error);
+                }
+                for warning in &report.warnings {
+                    ui.colored_label(egui::Color32::YELLOW, warning);
+                }
+                egui::Grid::new("export_report_grid")
+                    .num_columns(6)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("表名");
+                        ui.strong("来源");
+                        ui.strong("分组");
+                        ui.strong("导出目标");
+                        ui.strong("导出行数");
+                        ui.strong("版本过滤行数");
+                        ui.end_row();
+                        for table in &report.tables {
+                            ui.label(&table.name);
+                            ui.label(&table.source);
+                            ui.label(&table.group);
+                            ui.label(table.targets.join(","));
+                            ui.label(table.rows.to_string());
+                            ui.label(table.filtered_rows.to_string());
+                            ui.end_row();
+                        }
+                    });
+            });
+    });
+}
@@ -0,0 +1,197 @@
+use crate::export_target::ExportTarget;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, Error};
+use std::path::{Path, PathBuf};
+
+/// Excel目录下的导表规则文件, 不存在时使用空规则
+pub const EXPORT_RULES_FILE: &str = "export_rules.json";
+
+/// 导表规则, 配置导出目标, 以及按表名和字段名配置表头里放不下的列选项
+///
+/// ```json
+/// {
+///   "targets": [{ "name": "server" }, { "name": "client", "header": "client" }],
+///   "tables": { "Item": { "columns": { "count": { "default": "1", "required": true }, "name": { "width": 16 } } } },
+///   "translation": { "source_locale": "zh", "key_functions": ["tr"] }
+/// }
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct ExportRules {
+    pub targets: Vec<ExportTarget>,
+    pub tables: HashMap<String, TableRules>,
+    pub translation: TranslationRules,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct TranslationRules {
+    /// Language表中作为源文本的语言列, 为空时取第一个语言列
+    pub source_locale: Option<String>,
+    /// 客户端获取翻译的函数名, 这些函数的第一个字符串参数作为key检查是否存在于Language表;
+    /// 默认为 `tr`, 配置为空列表时不检查
+    pub key_functions: Vec<String>,
+}
+
+impl Default for TranslationRules {
+    fn default() -> Self {
+        Self {
+            source_locale: None,
+            key_functions: vec!["tr".to_string()],
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct TableRules {
+    pub columns: HashMap<String, ColumnRule>,
+}
+
+#[derive(Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct ColumnRule {
+    pub default: Option<String>,
+    pub required: bool,
+    pub width: Option<usize>,
+}
+
+impl ExportRules {
+    pub fn load(excel_dir: impl AsRef<Path>) -> io::Result<Self> {
+        let path = excel_dir.as_ref().join(EXPORT_RULES_FILE);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let json_string = std::fs::read_to_string(&path)?;
+        let rules: Self = serde_json::from_str(&json_string).map_err(|e| {
+            Error::new(
+                io::ErrorKind::Other,
+                format!("解析{}失败: {}", path.display(), e),
+            )
+        })?;
+        rules
+            .check_targets()
+            .map_err(|e| Error::new(io::ErrorKind::Other, format!("{}: {}", path.display(), e)))?;
+        Ok(rules)
+    }
+
+    /// 检查导出目标: 目标名不能为空或重复; 导出前会删除目标目录,
+    /// 所以目录必须是输出目录下的相对路径, 不能包含 `.`/`..`, 不同目标的目录不能相同或嵌套
+    fn check_targets(&self) -> io::Result<()> {
+        let invalid = |message: String| Err(Error::new(io::ErrorKind::Other, message));
+        let mut dirs: Vec<(String, &str)> = Vec::new();
+        for target in &self.targets {
+            if target.name.trim().is_empty() {
+                return invalid("导出目标名不能为空".to_string());
+            }
+            if self
+                .targets
+                .iter()
+                .filter(|t| t.name == target.name)
+                .count()
+                > 1
+            {
+                return invalid(format!("导出目标名重复: {}", target.name));
+            }
+            let dir = target.dir();
+            let parts: Vec<&str> = dir.split(['/', '\\']).filter(|p| !p.is_empty()).collect();
+            if Path::new(dir).is_absolute()
+                || dir.starts_with(['/', '\\'])
+                || dir.contains(':')
+                || parts.is_empty()
+                || parts.iter().any(|p| *p == "." || *p == "..")
+            {
+                return invalid(format!(
+                    "导出目标 {} 的目录 {} 无效, 必须是输出目录下的子目录",
+                    target.name, dir
+                ));
+            }
+            let dir = parts.join("/");
+            let nested = |a: &str, b: &str| a == b || a.starts_with(&format!("{}/", b));
+            if let Some((_, other)) = dirs
+                .iter()
+                .find(|(other_dir, _)| nested(&dir, other_dir) || nested(other_dir, &dir))
+            {
+                return invalid(format!(
+                    "导出目标 {} 和 {} 的目录 {} 相同或嵌套",
+                    other, target.name, dir
+                ));
+            }
+            dirs.push((dir, &target.name));
+        }
+        Ok(())
+    }
+
+    /// 配置的导出目标, 未配置时为默认的 server 和 client
+    pub fn export_targets(&self) -> Vec<ExportTarget> {
+        if self.targets.is_empty() {
+            ExportTarget::defaults()
+        } else {
+            self.targets.clone()
+        }
+    }
+
+    /// 导出目标的输出目录, 未配置该目标时为 `输出目录/目标名`
+    pub fn target_dir(&self, output_dir: impl AsRef<Path>, name: &str) -> PathBuf {
+        let targets = self.export_targets();
+        let dir = targets
+            .iter()
+            .find(|t| t.name == name)
+            .map_or(name, |t| t.dir());
+        output_dir.as_ref().join(dir)
+    }
+
+    pub fn column(&self, table: &str, column: &str) -> Option<&ColumnRule> {
+        self.tables.get(table)?.columns.get(column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(targets: &str) -> io::Result<()> {
+        let rules: ExportRules =
+            serde_json::from_str(&format!("{{\"targets\": {}}}", targets)).unwrap();
+        rules.check_targets()
+    }
+
+    #[test]
+    fn valid_targets() {
+        assert!(check(r#"[{"name": "server"}, {"name": "client", "dir": "out/client"}]"#).is_ok());
+        assert!(check(r#"[{"name": "gm", "dir": "gm/"}, {"name": "gm2", "dir": "gm2"}]"#).is_ok());
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert!(check(r#"[{"name": ""}]"#).is_err());
+        assert!(check(r#"[{"name": "server"}, {"name": "server", "dir": "b"}]"#).is_err());
+    }
+
+    #[test]
+    fn invalid_dirs() {
+        for dir in [
+            ".",
+            "..",
+            "./",
+            "a/../b",
+            "a/./b",
+            "/tmp/out",
+            "\\\\share",
+            "C:\\out",
+            "//",
+        ] {
+            let targets = serde_json::json!([{ "name": "server", "dir": dir }]).to_string();
+            assert!(check(&targets).is_err(), "{}", dir);
+        }
+        assert!(check(r#"[{"name": ".."}]"#).is_err());
+    }
+
+    #[test]
+    fn shared_or_nested_dirs() {
+        assert!(check(r#"[{"name": "a", "dir": "out"}, {"name": "b", "dir": "out/"}]"#).is_err());
+        assert!(check(r#"[{"name": "a", "dir": "out"}, {"name": "b", "dir": "out/b"}]"#).is_err());
+        assert!(check(r#"[{"name": "a", "dir": "out/a"}, {"name": "out"}]"#).is_err());
+    }
+}
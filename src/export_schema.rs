@@ -0,0 +1,213 @@
+use crate::export_target::{ExportTarget, HeaderStyle};
+use crate::file_utils::read_json_or_default;
+use crate::xlsx2csv::SheetTable;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::{fs, io, path};
+
+/// 输出目录下保存上次导出时每个导出目标中各表的列, 用于发现会导致加载失败的表结构变化
+pub const EXPORT_SCHEMA_FILE: &str = ".export_schema.json";
+/// 输出目录下尚未确认的表结构变更, 同步服务器前必须确认
+pub const PENDING_SCHEMA_CHANGES_FILE: &str = ".schema_changes_pending.json";
+/// 同步前需要确认表结构变更的导出目标, 其他目标的变更只在导出报告中警告
+pub const SYNC_GATED_TARGET: &str = "server";
+
+/// 导出目标 -> 表的输出路径 -> 导出的列
+pub type ExportSchema = BTreeMap<String, BTreeMap<String, Vec<SchemaColumn>>>;
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaColumn {
+    pub name: String,
+    /// 按导出目标的表头样式取服务器或客户端类型
+    pub column_type: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SchemaChangeKind {
+    ColumnAdded,
+    ColumnRemoved,
+    ColumnRenamed,
+    TypeChanged,
+}
+
+impl SchemaChangeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SchemaChangeKind::ColumnAdded => "新增列",
+            SchemaChangeKind::ColumnRemoved => "删除列",
+            SchemaChangeKind::ColumnRenamed => "列改名",
+            SchemaChangeKind::TypeChanged => "类型变化",
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub target: String,
+    pub table: String,
+    pub kind: SchemaChangeKind,
+    pub detail: String,
+}
+
+impl SchemaChange {
+    pub fn message(&self) -> String {
+        format!(
+            "[{}] {} {}: {}",
+            self.target,
+            self.table,
+            self.kind.label(),
+            self.detail
+        )
+    }
+}
+
+/// 表在一个导出目标中导出的列
+pub fn table_schema(table: &SheetTable, target: &ExportTarget) -> Vec<SchemaColumn> {
+    table
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(i, column)| target.includes_column(*i, &column.column_type))
+        .map(|(_, column)| SchemaColumn {
+            name: column.name.clone(),
+            column_type: match target.header {
+                HeaderStyle::Server => column.column_type.server.clone(),
+                HeaderStyle::Client => column.column_type.client.clone(),
+            },
+        })
+        .collect()
+}
+
+pub fn load_schema(output_dir: &path::Path) -> ExportSchema {
+    read_json_or_default(output_dir.join(EXPORT_SCHEMA_FILE))
+}
+
+pub fn save_schema(output_dir: &path::Path, schema: &ExportSchema) -> io::Result<()> {
+    fs::write(
+        output_dir.join(EXPORT_SCHEMA_FILE),
+        serde_json::to_string_pretty(schema)?,
+    )
+}
+
+/// 对比两次导出都存在的表的列; 删除一列的同时在同一位置新增一列且类型相同时视为改名
+pub fn diff_schema(previous: &ExportSchema, current: &ExportSchema) -> Vec<SchemaChange> {
+    let mut changes = Vec::new();
+    for (target, tables) in current {
+        let Some(previous_tables) = previous.get(target) else {
+            continue;
+        };
+        for (table, columns) in tables {
+            let Some(previous_columns) = previous_tables.get(table) else {
+                continue;
+            };
+            let mut push = |kind, detail| {
+                changes.push(SchemaChange {
+                    target: target.clone(),
+                    table: table.clone(),
+                    kind,
+                    detail,
+                })
+            };
+
+            let find =
+                |columns: &[SchemaColumn], name: &str| columns.iter().position(|c| c.name == name);
+            let removed: Vec<usize> = (0..previous_columns.len())
+                .filter(|&i| find(columns, &previous_columns[i].name).is_none())
+                .collect();
+            let mut added: Vec<usize> = (0..columns.len())
+                .filter(|&i| find(previous_columns, &columns[i].name).is_none())
+                .collect();
+
+            // 前面最近的一个两次都存在的列, 改名的列前后两次应该跟在同一列后面
+            let anchor = |columns: &[SchemaColumn], index: usize, other: &[SchemaColumn]| {
+                columns[..index]
+                    .iter()
+                    .rev()
+                    .find(|c| find(other, &c.name).is_some())
+                    .map(|c| c.name.clone())
+            };
+            for i in removed {
+                let old = &previous_columns[i];
+                let old_anchor = anchor(previous_columns, i, columns);
+                let renamed = added.iter().position(|&j| {
+                    columns[j].column_type == old.column_type
+                        && anchor(columns, j, previous_columns) == old_anchor
+                });
+                match renamed {
+                    Some(position) => {
+                        let new = &columns[added.remove(position)];
+                        push(
+                            SchemaChangeKind::ColumnRenamed,
+                            format!("{} -> {}", old.name, new.name),
+                        );
+                    }
+                    None => push(SchemaChangeKind::ColumnRemoved, old.name.clone()),
+                }
+            }
+            for j in added {
+                push(SchemaChangeKind::ColumnAdded, columns[j].name.clone());
+            }
+            for column in columns {
+                let Some(i) = find(previous_columns, &column.name) else {
+                    continue;
+                };
+                if previous_columns[i].column_type != column.column_type {
+                    push(
+                        SchemaChangeKind::TypeChanged,
+                        format!(
+                            "{}: {} -> {}",
+                            column.name, previous_columns[i].column_type, column.column_type
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    changes
+}
+
+/// 尚未确认的表结构变更
+pub fn pending_changes(output_dir: &path::Path) -> Vec<SchemaChange> {
+    read_json_or_default(output_dir.join(PENDING_SCHEMA_CHANGES_FILE))
+}
+
+/// 本次导出中需要确认的导出目标的表结构变更追加到未确认的变更中,
+/// 同时清理之前记录的其他目标的变更
+pub fn add_pending_changes(output_dir: &path::Path, changes: &[SchemaChange]) -> io::Result<()> {
+    let mut pending = pending_changes(output_dir);
+    let recorded = pending.len();
+    pending.retain(|change| change.target == SYNC_GATED_TARGET);
+    let gated: Vec<&SchemaChange> = changes
+        .iter()
+        .filter(|change| change.target == SYNC_GATED_TARGET)
+        .collect();
+    if gated.is_empty() && pending.len() == recorded {
+        return Ok(());
+    }
+    for change in gated {
+        if !pending.contains(change) {
+            pending.push(change.clone());
+        }
+    }
+    fs::write(
+        output_dir.join(PENDING_SCHEMA_CHANGES_FILE),
+        serde_json::to_string_pretty(&pending)?,
+    )
+}
+
+/// 确认一个导出目标的表结构变更
+pub fn acknowledge_changes(output_dir: &path::Path, target: &str) -> io::Result<()> {
+    let pending: Vec<SchemaChange> = pending_changes(output_dir)
+        .into_iter()
+        .filter(|change| change.target != target)
+        .collect();
+    let path = output_dir.join(PENDING_SCHEMA_CHANGES_FILE);
+    if pending.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    } else {
+        fs::write(path, serde_json::to_string_pretty(&pending)?)
+    }
+}
@@ -0,0 +1,146 @@
+use crate::column_type::ColumnType;
+use crate::table_meta::{TableFormat, TableMeta};
+use serde::Deserialize;
+
+/// 导出目标, 在导表规则文件的 `targets` 中配置, 未配置时使用默认的 server 和 client
+///
+/// ```json
+/// { "name": "gm-web", "dir": "gm", "format": "json", "header": "client", "columns": "marked", "target_version": "1.3" }
+/// ```
+///
+/// A1中标记了目标名的表只导出到这些目标, 未标记的表导出到 `all_tables` 为 true(默认)的目标;
+/// 类型行中 `|targets=server+gm-web` 标记的列只导出到这些目标, 未标记的列导出到
+/// `columns` 为 all 的目标, 第一列(主键)总是导出
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ExportTarget {
+    pub name: String,
+    /// 相对输出目录的子目录, 为空时使用目标名
+    pub dir: String,
+    pub format: TableFormat,
+    pub header: HeaderStyle,
+    pub columns: ColumnFilter,
+    pub all_tables: bool,
+    /// 按 SINCE_VERSION/UNTIL_VERSION 过滤行的目标版本, 为空时使用导出设置中的目标版本
+    pub target_version: String,
+    /// 额外的生成器, 例如 Language 表的 translation_ts、BadWords 表的 badwords_automaton、所有表的 binary_tables
+    pub generators: Vec<String>,
+    /// protobuf 生成器写入 `.proto` 的包名, 为空时不声明包
+    pub proto_package: String,
+}
+
+/// 表头样式: server 带注释行并使用服务器类型, client 使用客户端类型
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeaderStyle {
+    #[default]
+    Server,
+    Client,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnFilter {
+    /// 导出未标记的列和标记了本目标的列
+    #[default]
+    All,
+    /// 只导出标记了本目标的列
+    Marked,
+}
+
+pub const GENERATOR_TRANSLATION_TS: &str = "translation_ts";
+/// 按语言拆分的翻译文件, 见 `translation::write_locale_files`
+pub const GENERATOR_TRANSLATION_LOCALES_TS: &str = "translation_locales_ts";
+pub const GENERATOR_TRANSLATION_LOCALES_JSON: &str = "translation_locales_json";
+/// 翻译key的联合类型, 见 `translation_keys::write_translation_keys_ts`
+pub const GENERATOR_TRANSLATION_KEYS_TS: &str = "translation_keys_ts";
+/// BadWords 表预编译的敏感词自动机, 见 `bad_words::Automaton`
+pub const GENERATOR_BAD_WORDS_AUTOMATON: &str = "badwords_automaton";
+/// 所有表额外导出的二进制表和TypeScript读取器, 需要在导出目标中配置; 拆分的表不导出,
+/// 见 `binary_table::write_binary_table`
+pub const GENERATOR_BINARY_TABLES: &str = "binary_tables";
+/// 所有表的 `.proto` 和序列化后的数据, 见 `protobuf::write_protobuf_table`
+pub const GENERATOR_PROTOBUF: &str = "protobuf";
+/// 导出到目标的所有表写入一个SQLite数据库, 见 `sqlite_export::write_database`
+pub const GENERATOR_SQLITE: &str = "sqlite";
+pub const GENERATORS: &[&str] = &[
+    GENERATOR_TRANSLATION_TS,
+    GENERATOR_TRANSLATION_LOCALES_TS,
+    GENERATOR_TRANSLATION_LOCALES_JSON,
+    GENERATOR_TRANSLATION_KEYS_TS,
+    GENERATOR_BAD_WORDS_AUTOMATON,
+    GENERATOR_BINARY_TABLES,
+    GENERATOR_PROTOBUF,
+    GENERATOR_SQLITE,
+];
+
+impl Default for ExportTarget {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            dir: String::new(),
+            format: TableFormat::Csv,
+            header: HeaderStyle::Server,
+            columns: ColumnFilter::All,
+            all_tables: true,
+            target_version: String::new(),
+            generators: Vec::new(),
+            proto_package: String::new(),
+        }
+    }
+}
+
+impl ExportTarget {
+    /// 未配置导出目标时的默认目标, 与旧版固定输出的 server 和 client 目录一致
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self {
+                name: "server".to_string(),
+                all_tables: true,
+                generators: vec![GENERATOR_BAD_WORDS_AUTOMATON.to_string()],
+                ..Default::default()
+            },
+            Self {
+                name: "client".to_string(),
+                header: HeaderStyle::Client,
+                all_tables: true,
+                generators: vec![
+                    GENERATOR_TRANSLATION_TS.to_string(),
+                    GENERATOR_TRANSLATION_LOCALES_TS.to_string(),
+                    GENERATOR_TRANSLATION_KEYS_TS.to_string(),
+                    GENERATOR_BAD_WORDS_AUTOMATON.to_string(),
+                ],
+                ..Default::default()
+            },
+        ]
+    }
+
+    pub fn dir(&self) -> &str {
+        if self.dir.is_empty() {
+            &self.name
+        } else {
+            &self.dir
+        }
+    }
+
+    pub fn includes_table(&self, meta: &TableMeta) -> bool {
+        match &meta.targets {
+            Some(targets) => targets.contains(&self.name),
+            None => self.all_tables,
+        }
+    }
+
+    pub fn includes_column(&self, column_index: usize, column_type: &ColumnType) -> bool {
+        if column_index == 0 {
+            return true;
+        }
+        match &column_type.targets {
+            Some(targets) => targets.contains(&self.name),
+            None => self.columns == ColumnFilter::All,
+        }
+    }
+
+    pub fn has_generator(&self, generator: &str) -> bool {
+        self.generators.iter().any(|g| g == generator)
+    }
+}
@@ -1,3 +1,5 @@
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
 use std::fs::{self};
 use std::io::{self};
 use std::path::Path;
@@ -32,27 +34,70 @@ pub fn copy_file(src: impl AsRef<Path>, dst: impl AsRef<Path>) -> io::Result<()>
     Ok(())
 }
 
-pub fn copy_dir_files_no_subdir(
+/// 递归列出目录下满足条件的文件, 返回以`/`分隔的相对路径并排序;
+/// 跳过以`.`或`~`开头的文件和目录(隐藏文件和Office临时文件)
+pub fn list_files_recursive(
+    dir: impl AsRef<Path>,
+    filter: impl Fn(&Path) -> bool,
+) -> io::Result<Vec<String>> {
+    fn walk(
+        dir: &Path,
+        prefix: &str,
+        filter: &dyn Fn(&Path) -> bool,
+        files: &mut Vec<String>,
+    ) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            if name.starts_with('.') || name.starts_with('~') {
+                continue;
+            }
+
+            let relative = format!("{}{}", prefix, name);
+            if path.is_dir() {
+                walk(&path, &format!("{}/", relative), filter, files)?;
+            } else if path.is_file() && filter(&path) {
+                files.push(relative);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(dir.as_ref(), "", &filter, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// 递归复制目录下的文件, 保持子目录结构
+pub fn copy_dir_files(
     src_dir: impl AsRef<Path>,
     dst_dir: impl AsRef<Path>,
-    skip_filter: impl Fn(&fs::DirEntry) -> bool,
+    skip_filter: impl Fn(&Path) -> bool,
 ) -> io::Result<()> {
     let src_dir = src_dir.as_ref();
     let dst_dir = dst_dir.as_ref();
-    
-    // 确保目标目录存在
-    fs::create_dir_all(dst_dir)?;
-    
-    for entry in fs::read_dir(src_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        
-        if path.is_file() && !skip_filter(&entry) {
-            let file_name = path.file_name().unwrap();
-            let dst_path = dst_dir.join(file_name);
-            fs::copy(&path, dst_path)?;
-        }
+    for relative in list_files_recursive(src_dir, |path| !skip_filter(path))? {
+        copy_file(src_dir.join(&relative), dst_dir.join(&relative))?;
     }
-    
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 读取输出目录下保存的状态文件, 不存在或解析失败时返回默认值
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: impl AsRef<Path>) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|json_string| serde_json::from_str(&json_string).ok())
+        .unwrap_or_default()
+}
+
+/// 计算SHA-256并返回小写十六进制字符串
+pub fn sha256_hex(data: impl AsRef<[u8]>) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
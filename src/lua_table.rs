@@ -0,0 +1,84 @@
+use crate::xlsx2csv::{SheetRow, SheetTable, is_identifier};
+use serde_json::Value;
+use std::fmt::Write as _;
+
+const LUA_KEYWORDS: &[&str] = &[
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Lua字符串字面量: 转义引号、反斜杠和控制字符, 其他字符按UTF-8原样写入
+pub fn lua_string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c if c.is_ascii_control() => {
+                let _ = write!(literal, "\\{:03}", c as u32);
+            }
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn lua_value(value: &Value) -> String {
+    match value {
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => lua_string(s),
+        _ => "nil".to_string(),
+    }
+}
+
+/// 字段名是合法标识符时写作 `name = `, 否则写作 `["name"] = `
+fn lua_field(name: &str) -> String {
+    if is_identifier(name) && !LUA_KEYWORDS.contains(&name) {
+        name.to_string()
+    } else {
+        format!("[{}]", lua_string(name))
+    }
+}
+
+/// 导出为返回以主键为键的表的Lua模块, 值按类型行转换, 无法转换时保留字符串;
+/// 单元格按原样写入, 不做CSV的逗号和换行转义
+pub fn table_lua(table: &SheetTable, rows: &[&SheetRow], columns: &[usize]) -> String {
+    let mut content = String::new();
+    let _ = writeln!(
+        content,
+        "-- 由导表工具根据 {} 生成, 请勿手动修改",
+        table.source
+    );
+    content.push_str("return {\n");
+    for row in rows {
+        let values: Vec<Value> = columns
+            .iter()
+            .map(|&i| table.columns[i].column_type.json_value(&row.values[i]))
+            .collect();
+        let fields: Vec<String> = columns
+            .iter()
+            .zip(&values)
+            .map(|(&i, value)| {
+                format!(
+                    "{} = {}",
+                    lua_field(&table.columns[i].name),
+                    lua_value(value)
+                )
+            })
+            .collect();
+        let _ = writeln!(
+            content,
+            "    [{}] = {{ {} }},",
+            lua_value(&values[0]),
+            fields.join(", ")
+        );
+    }
+    content.push_str("}\n");
+    content
+}
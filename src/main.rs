@@ -19,6 +19,7 @@ async fn main() -> eframe::Result<()> {
 }
 
 #[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
 struct App {
     #[serde(skip)]
     current_tab: usize,
@@ -26,6 +27,9 @@ struct App {
     output_dir: String,
     client_dir: String,
     server_dir: String,
+    target_version: String,
+    mirror_folders: bool,
+    fix_unicode: bool,
     #[serde(skip)]
     files: Vec<String>,
     #[serde(skip)]
@@ -43,6 +47,16 @@ struct App {
     #[serde(skip)]
     export_progress: Option<(i32, i32, String)>,
     #[serde(skip)]
+    export_report: Option<export_report::ExportReport>,
+    #[serde(skip)]
+    translation_import: Option<translation_exchange::ImportReport>,
+    #[serde(skip)]
+    data_import: Vec<data_import::DataImportReport>,
+    #[serde(skip)]
+    pending_schema_changes: Option<Vec<export_schema::SchemaChange>>,
+    #[serde(skip)]
+    last_export_errors: Option<Vec<String>>,
+    #[serde(skip)]
     sync_server_progress: Option<(i32, i32, String)>,
     #[serde(skip)]
     server_restart: bool,
@@ -52,6 +66,7 @@ pub enum AppNotice {
     Toast((String, u64)),
     ToastErr((String, u64)),
     ExportProgress(i32, i32, String),
+    ExportReport(export_report::ExportReport),
     SyncServerProgress(i32, i32, String),
     ServerRestartStart,
     ServerRestartComplete,
@@ -96,13 +111,35 @@ impl App {
         Some(())
     }
 }
+mod bad_words;
+mod binary_table;
+mod client_assets;
+mod column_type;
+mod data_import;
+mod export_diff;
 mod export_files;
+mod export_manifest;
+mod export_report;
+mod export_rules;
+mod export_schema;
+mod export_target;
 mod file_utils;
+mod lua_table;
 mod minio_uploader;
+mod protobuf;
 mod server;
+mod sqlite_export;
 mod ssh_utils;
 mod sync_client;
 mod sync_server;
+mod table_chunks;
+mod table_meta;
+mod table_template;
+mod text_width;
+mod translation;
+mod translation_exchange;
+mod translation_keys;
+mod unicode_hygiene;
 mod xlsx2csv;
 
 impl eframe::App for App {
@@ -124,6 +161,12 @@ impl eframe::App for App {
                         }
                         AppNotice::ExportProgress(cur, total, text) => {
                             self.export_progress = Some((cur, total, text));
+                            self.last_export_errors = None;
+                        }
+                        AppNotice::ExportReport(report) => {
+                            self.export_report = Some(report);
+                            self.pending_schema_changes = None;
+                            self.last_export_errors = None;
                         }
                         AppNotice::SyncServerProgress(cur, total, text) => {
                             self.sync_server_progress = Some((cur, total, text));
@@ -187,6 +230,18 @@ impl eframe::App for App {
                     ui.add_space(10.0);
                     ui.separator();
                     ui.add_space(10.0);
+                    translation_exchange::translation_exchange_ui(self, ui);
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+                    table_template::table_template_ui(self, ui);
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
+                    data_import::data_import_ui(self, ui);
+                    ui.add_space(10.0);
+                    ui.separator();
+                    ui.add_space(10.0);
                     sync_client::sync_client_ui(self, ui);
                     ui.add_space(10.0);
                     ui.separator();
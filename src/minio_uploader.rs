@@ -1,4 +1,6 @@
 use crate::AppNotice;
+use crate::export_manifest::MANIFEST_FILE;
+use crate::file_utils::list_files_recursive;
 use anyhow::anyhow;
 use minio_rsc::Minio;
 use minio_rsc::provider::StaticProvider;
@@ -37,10 +39,9 @@ pub async fn minio_upload(
             .map_err(|e| anyhow!("Error make bucket: {}", e))?;
     }
 
-    // 先统计csv文件总数
-    let mut total_csv_files = 0;
-    let mut count_entries = match fs::read_dir(input_dir.clone()).await {
-        Ok(entries) => entries,
+    // 递归列出所有文件(子目录中的文件以相对路径作为key), 先统计csv文件总数
+    let mut files = match list_files_recursive(&input_dir, |_| true) {
+        Ok(files) => files,
         Err(e) => {
             sender
                 .send(AppNotice::Toast((
@@ -51,16 +52,9 @@ pub async fn minio_upload(
             return Err(anyhow::anyhow!("Failed to read directory {}", input_dir).into());
         }
     };
-
-    while let Some(entry) = count_entries
-        .next_entry()
-        .await
-        .map_err(|e| anyhow!("Failed to read directory entry: {}", e))?
-    {
-        if entry.path().is_file() && entry.path().extension().map_or(false, |ext| ext == "csv") {
-            total_csv_files += 1;
-        }
-    }
+    // 清单最后上传, 服务器读到新清单时其中的文件都已上传完成
+    files.sort_by_key(|f| f == MANIFEST_FILE);
+    let total_csv_files = files.iter().filter(|f| f.ends_with(".csv")).count() as i32;
 
     sender
         .send(AppNotice::SyncServerProgress(
@@ -71,75 +65,48 @@ pub async fn minio_upload(
         .unwrap();
 
     let mut current_file = 0;
-    let mut entries = match fs::read_dir(input_dir.clone()).await {
-        Ok(entries) => entries,
-        Err(e) => {
-            sender
-                .send(AppNotice::Toast((
-                    format!("Failed to read directory {}: {}", input_dir, e),
-                    5,
-                )))
-                .unwrap();
-            return Err(anyhow::anyhow!("Failed to read directory {}", input_dir).into());
-        }
-    };
+    for relative_path in files {
+        let path = std::path::Path::new(&input_dir).join(&relative_path);
+        let dest_path = path.to_string_lossy().into_owned();
+        let src_key = format!("{}{}", COS_KEY_FORMATTER, relative_path);
 
-    while let Some(entry) = match entries.next_entry().await {
-        Ok(entry) => entry,
-        Err(e) => {
-            sender
-                .send(AppNotice::Toast((
-                    format!("Failed to read directory entry: {}", e),
-                    5,
-                )))
-                .unwrap();
-            return Err(anyhow::anyhow!("Failed to read directory entry").into());
-        }
-    } {
-        let path = entry.path();
-        if path.is_file() {
-            let file_name = path.file_name().unwrap().to_string_lossy().into_owned();
-            let dest_path = path.to_string_lossy().into_owned();
-            let src_key = format!("{}{}", COS_KEY_FORMATTER, file_name);
-
-            let mut file = match fs::File::open(&path).await {
-                Ok(file) => file,
-                Err(e) => {
-                    sender
-                        .send(AppNotice::Toast((
-                            format!("Failed to open file {}: {}", dest_path, e),
-                            5,
-                        )))
-                        .unwrap();
-                    continue;
-                }
-            };
-
-            let mut contents = vec![];
-            if let Err(e) = file.read_to_end(&mut contents).await {
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) => {
                 sender
                     .send(AppNotice::Toast((
-                        format!("Failed to read file {}: {}", dest_path, e),
+                        format!("Failed to open file {}: {}", dest_path, e),
                         5,
                     )))
                     .unwrap();
                 continue;
             }
-            if path.extension().map_or(false, |ext| ext == "csv") {
-                current_file += 1;
-                sender
-                    .send(AppNotice::SyncServerProgress(
-                        current_file,
-                        total_csv_files,
-                        dest_path,
-                    ))
-                    .unwrap();
-            }
-            minio
-                .put_object(&bucket_name, &src_key, contents.into())
-                .await
-                .map_err(|e| anyhow!("{}", e))?;
+        };
+
+        let mut contents = vec![];
+        if let Err(e) = file.read_to_end(&mut contents).await {
+            sender
+                .send(AppNotice::Toast((
+                    format!("Failed to read file {}: {}", dest_path, e),
+                    5,
+                )))
+                .unwrap();
+            continue;
         }
+        if path.extension().is_some_and(|ext| ext == "csv") {
+            current_file += 1;
+            sender
+                .send(AppNotice::SyncServerProgress(
+                    current_file,
+                    total_csv_files,
+                    dest_path,
+                ))
+                .unwrap();
+        }
+        minio
+            .put_object(&bucket_name, &src_key, contents.into())
+            .await
+            .map_err(|e| anyhow!("{}", e))?;
     }
 
     Ok(())
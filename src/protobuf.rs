@@ -0,0 +1,363 @@
+use crate::column_type::parse_bool;
+use crate::export_diff::table_id;
+use crate::export_target::ExportTarget;
+use crate::file_utils::read_json_or_default;
+use crate::xlsx2csv::{SheetTable, cell_name, is_identifier, target_columns};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::{fs, io, path};
+
+/// 输出目录下保存各导出目标中每张表的字段编号, 列的顺序变化或删除后已用的编号不会被重新分配
+pub const PROTO_FIELDS_FILE: &str = ".proto_fields.json";
+
+/// 导出目标 -> 表的输出路径 -> 列名 -> 字段编号, 包括已删除的列
+pub type ProtoFields = BTreeMap<String, BTreeMap<String, BTreeMap<String, u32>>>;
+
+/// 由服务器类型决定的protobuf字段类型
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ProtoType {
+    Int32,
+    Int64,
+    Float,
+    Double,
+    Bool,
+    String,
+}
+
+impl ProtoType {
+    fn from_type_name(name: &str) -> Self {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "int" | "short" | "byte" => ProtoType::Int32,
+            "long" => ProtoType::Int64,
+            "float" => ProtoType::Float,
+            "double" | "number" => ProtoType::Double,
+            "bool" | "boolean" => ProtoType::Bool,
+            _ => ProtoType::String,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ProtoType::Int32 => "int32",
+            ProtoType::Int64 => "int64",
+            ProtoType::Float => "float",
+            ProtoType::Double => "double",
+            ProtoType::Bool => "bool",
+            ProtoType::String => "string",
+        }
+    }
+}
+
+pub fn load_fields(output_dir: &path::Path) -> ProtoFields {
+    read_json_or_default(output_dir.join(PROTO_FIELDS_FILE))
+}
+
+pub fn save_fields(output_dir: &path::Path, fields: &ProtoFields) -> io::Result<()> {
+    fs::write(
+        output_dir.join(PROTO_FIELDS_FILE),
+        serde_json::to_string_pretty(fields)?,
+    )
+}
+
+/// 按导出目标的列写出表的 `.proto` 和序列化后的数据 `.pb`:
+/// 每行为一个以表名命名的消息, 所有行为 `表名Table` 消息的 `rows` 字段;
+/// 字段编号按列名记录在 `fields` 中保持不变, 已删除列的编号标记为 reserved;
+/// 表名或列名不是合法标识符、单元格无法按类型解析时报告错误
+pub fn write_protobuf_table(
+    table: &SheetTable,
+    target: &ExportTarget,
+    dir: &path::Path,
+    fields: &mut ProtoFields,
+    errors: &mut Vec<String>,
+) -> io::Result<()> {
+    let name = &table.meta.name;
+    if !is_identifier(name) {
+        errors.push(format!(
+            "<<{}>> 表名 {} 不是合法的protobuf消息名, 跳过protobuf导出",
+            table.source, name
+        ));
+        return Ok(());
+    }
+    let columns = target_columns(table, target);
+    let invalid: Vec<&str> = columns
+        .iter()
+        .map(|&i| table.columns[i].name.as_str())
+        .filter(|name| !is_identifier(name))
+        .collect();
+    if !invalid.is_empty() {
+        errors.push(format!(
+            "<<{}>> 列名 {} 不是合法的protobuf字段名, 跳过protobuf导出",
+            table.source,
+            invalid.join(", ")
+        ));
+        return Ok(());
+    }
+
+    let numbers = fields
+        .entry(target.name.clone())
+        .or_default()
+        .entry(table_id(table))
+        .or_default();
+    let names: Vec<&str> = columns
+        .iter()
+        .map(|&i| table.columns[i].name.as_str())
+        .collect();
+    let reserved = assign_field_numbers(numbers, &names);
+    let proto_fields: Vec<(usize, ProtoType, u32)> = columns
+        .iter()
+        .map(|&i| {
+            let column = &table.columns[i];
+            (
+                i,
+                ProtoType::from_type_name(&column.column_type.server),
+                numbers[&column.name],
+            )
+        })
+        .collect();
+
+    let mut proto = String::new();
+    let _ = writeln!(
+        proto,
+        "// 由导表工具根据 {} 生成, 请勿手动修改",
+        table.source
+    );
+    proto.push_str("syntax = \"proto3\";\n\n");
+    if !target.proto_package.is_empty() {
+        let _ = writeln!(proto, "package {};\n", target.proto_package);
+    }
+    let _ = writeln!(proto, "message {} {{", name);
+    if !reserved.is_empty() {
+        let reserved: Vec<String> = reserved.iter().map(u32::to_string).collect();
+        let _ = writeln!(proto, "  reserved {};", reserved.join(", "));
+    }
+    for &(i, proto_type, number) in &proto_fields {
+        let column = &table.columns[i];
+        let comment = column.comment.replace(['\r', '\n'], " ");
+        let _ = write!(
+            proto,
+            "  {} {} = {};",
+            proto_type.name(),
+            column.name,
+            number
+        );
+        if i > 0 && !comment.trim().is_empty() {
+            let _ = write!(proto, " // {}", comment.trim());
+        }
+        proto.push('\n');
+    }
+    proto.push_str("}\n\n");
+    let _ = writeln!(proto, "message {}Table {{", name);
+    let _ = writeln!(proto, "  repeated {} rows = 1;", name);
+    proto.push_str("}\n");
+
+    let mut data = Vec::new();
+    for row in &table.rows {
+        let mut message = Vec::new();
+        for &(i, proto_type, number) in &proto_fields {
+            let value = &row.values[i];
+            if !encode_field(&mut message, number, proto_type, value) {
+                errors.push(format!(
+                    "<<{}>> {} {}: 无法按类型 {} 解析 {:?}, protobuf中使用默认值",
+                    table.source,
+                    cell_name(table.columns[i].cell_index, row.line),
+                    table.columns[i].name,
+                    proto_type.name(),
+                    value
+                ));
+            }
+        }
+        write_key(&mut data, 1, WIRE_LEN);
+        write_varint(&mut data, message.len() as u64);
+        data.extend_from_slice(&message);
+    }
+
+    let dir = dir.join(&table.output_dir);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(format!("{}.proto", name)), proto)?;
+    fs::write(dir.join(format!("{}.pb", name)), data)
+}
+
+/// 为列分配字段编号: 已有的列保持原编号, 新的列使用比已分配的编号都大的编号;
+/// 返回已删除的列的编号, 按升序排列
+fn assign_field_numbers(numbers: &mut BTreeMap<String, u32>, columns: &[&str]) -> Vec<u32> {
+    for column in columns {
+        let next = numbers.values().max().copied().unwrap_or(0) + 1;
+        numbers.entry(column.to_string()).or_insert(next);
+    }
+    let mut reserved: Vec<u32> = numbers
+        .iter()
+        .filter(|(column, _)| !columns.contains(&column.as_str()))
+        .map(|(_, &number)| number)
+        .collect();
+    reserved.sort();
+    reserved
+}
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+fn write_key(buffer: &mut Vec<u8>, number: u32, wire_type: u8) {
+    write_varint(buffer, ((number as u64) << 3) | wire_type as u64);
+}
+
+/// 编码一个字段, 与proto3一致省略默认值; 无法解析时不写入(即默认值)并返回 false
+fn encode_field(buffer: &mut Vec<u8>, number: u32, proto_type: ProtoType, value: &str) -> bool {
+    let trimmed = value.trim();
+    match proto_type {
+        ProtoType::Int32 | ProtoType::Int64 => {
+            let parsed = match proto_type {
+                ProtoType::Int32 => trimmed.parse::<i32>().map(i64::from).ok(),
+                _ => trimmed.parse::<i64>().ok(),
+            };
+            let Some(int) = parsed else {
+                return false;
+            };
+            if int != 0 {
+                write_key(buffer, number, WIRE_VARINT);
+                // 负数按补码编码为10字节
+                write_varint(buffer, int as u64);
+            }
+        }
+        ProtoType::Float => {
+            let Ok(float) = trimmed.parse::<f32>() else {
+                return false;
+            };
+            if float != 0.0 {
+                write_key(buffer, number, WIRE_FIXED32);
+                buffer.extend_from_slice(&float.to_le_bytes());
+            }
+        }
+        ProtoType::Double => {
+            let Ok(double) = trimmed.parse::<f64>() else {
+                return false;
+            };
+            if double != 0.0 {
+                write_key(buffer, number, WIRE_FIXED64);
+                buffer.extend_from_slice(&double.to_le_bytes());
+            }
+        }
+        ProtoType::Bool => {
+            let Some(bool) = parse_bool(trimmed) else {
+                return false;
+            };
+            if bool {
+                write_key(buffer, number, WIRE_VARINT);
+                write_varint(buffer, 1);
+            }
+        }
+        ProtoType::String => {
+            if !value.is_empty() {
+                write_key(buffer, number, WIRE_LEN);
+                write_varint(buffer, value.len() as u64);
+                buffer.extend_from_slice(value.as_bytes());
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(proto_type: ProtoType, value: &str) -> Option<Vec<u8>> {
+        let mut buffer = Vec::new();
+        encode_field(&mut buffer, 1, proto_type, value).then_some(buffer)
+    }
+
+    #[test]
+    fn varints() {
+        let varint = |value: u64| {
+            let mut buffer = Vec::new();
+            write_varint(&mut buffer, value);
+            buffer
+        };
+        assert_eq!(varint(0), [0x00]);
+        assert_eq!(varint(1), [0x01]);
+        assert_eq!(varint(150), [0x96, 0x01]);
+        assert_eq!(varint(u64::MAX).len(), 10);
+
+        let mut key = Vec::new();
+        write_key(&mut key, 16, WIRE_LEN);
+        assert_eq!(key, [0x82, 0x01]);
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(
+            encode(ProtoType::Int32, "150"),
+            Some(vec![0x08, 0x96, 0x01])
+        );
+        assert_eq!(encode(ProtoType::Int32, " 0 "), Some(vec![]));
+        // 负数按64位补码编码为10字节的varint
+        assert_eq!(
+            encode(ProtoType::Int32, "-1"),
+            Some(vec![
+                0x08, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01
+            ])
+        );
+        assert_eq!(encode(ProtoType::Int64, "-2").unwrap().len(), 11);
+        assert_eq!(encode(ProtoType::Int32, "3000000000"), None);
+        assert_eq!(
+            encode(ProtoType::Int64, "3000000000"),
+            Some(vec![0x08, 0x80, 0xbc, 0xc1, 0x96, 0x0b])
+        );
+        assert_eq!(encode(ProtoType::Int32, "1.5"), None);
+    }
+
+    #[test]
+    fn floats_and_bools() {
+        let mut float = vec![0x0d];
+        float.extend_from_slice(&1.5f32.to_le_bytes());
+        assert_eq!(encode(ProtoType::Float, "1.5"), Some(float));
+        let mut double = vec![0x09];
+        double.extend_from_slice(&(-0.25f64).to_le_bytes());
+        assert_eq!(encode(ProtoType::Double, "-0.25"), Some(double));
+        assert_eq!(encode(ProtoType::Double, "0"), Some(vec![]));
+        assert_eq!(encode(ProtoType::Float, "abc"), None);
+
+        assert_eq!(encode(ProtoType::Bool, "true"), Some(vec![0x08, 0x01]));
+        assert_eq!(encode(ProtoType::Bool, "1"), Some(vec![0x08, 0x01]));
+        assert_eq!(encode(ProtoType::Bool, "false"), Some(vec![]));
+        assert_eq!(encode(ProtoType::Bool, "maybe"), None);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(
+            encode(ProtoType::String, "中"),
+            Some(vec![0x0a, 0x03, 0xe4, 0xb8, 0xad])
+        );
+        assert_eq!(encode(ProtoType::String, ""), Some(vec![]));
+    }
+
+    #[test]
+    fn field_numbers_are_reused_and_reserved() {
+        let mut numbers = BTreeMap::new();
+        assert!(assign_field_numbers(&mut numbers, &["id", "name", "count"]).is_empty());
+        assert_eq!(numbers["id"], 1);
+        assert_eq!(numbers["name"], 2);
+        assert_eq!(numbers["count"], 3);
+
+        // 删除 name、调整顺序并新增 price: 保留的列编号不变, 新列不复用已删除列的编号
+        let reserved = assign_field_numbers(&mut numbers, &["id", "count", "price"]);
+        assert_eq!(reserved, [2]);
+        assert_eq!(numbers["count"], 3);
+        assert_eq!(numbers["price"], 4);
+
+        // 删除的列重新加回时使用原编号
+        let reserved = assign_field_numbers(&mut numbers, &["id", "name"]);
+        assert_eq!(reserved, [3, 4]);
+        assert_eq!(numbers["name"], 2);
+    }
+}
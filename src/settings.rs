@@ -75,6 +75,19 @@ pub fn settings_ui(app: &mut crate::App, ui: &mut egui::Ui) {
             }
         });
 
+        ui.add_space(5.0);
+
+        // 目标版本
+        ui.horizontal(|ui| {
+            ui.label("目标版本:(为空不按版本过滤, 导出目标可单独配置target_version)");
+            ui.add_space(10.0);
+            ui.add(egui::TextEdit::singleline(&mut app.target_version).desired_width(200.0));
+            ui.add_space(20.0);
+            ui.checkbox(&mut app.mirror_folders, "输出保持Excel子目录结构");
+            ui.add_space(20.0);
+            ui.checkbox(&mut app.fix_unicode, "修复不可见字符和非NFC文本");
+        });
+
         ui.add_space(10.0);
         if ui.button("保存配置").clicked() {
             if let Err(e) = app.save_config() {
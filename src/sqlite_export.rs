@@ -0,0 +1,145 @@
+use crate::column_type::{ValueKind, parse_bool};
+use crate::export_diff::table_id;
+use crate::export_target::{ExportTarget, HeaderStyle};
+use crate::xlsx2csv::{SheetTable, target_columns};
+use rusqlite::{Connection, params, types::Value};
+use std::collections::HashSet;
+use std::io::{self, Error};
+use std::{fs, path};
+
+/// 导出目标目录下的数据库文件, 包含导出到该目标的所有表
+pub const SQLITE_FILE: &str = "tables.db";
+/// 数据库中记录各表列注释和类型的表
+const COLUMNS_TABLE: &str = "_columns";
+
+fn sqlite_error(e: rusqlite::Error) -> Error {
+    Error::new(io::ErrorKind::Other, format!("写入SQLite失败: {}", e))
+}
+
+/// SQL标识符加双引号, 表名在保持目录结构时包含`/`
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn column_affinity(kind: ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Int | ValueKind::Bool => "INTEGER",
+        ValueKind::Float => "REAL",
+        ValueKind::String => "TEXT",
+    }
+}
+
+/// 按类型转换单元格, 无法转换时按文本存储
+fn sql_value(kind: ValueKind, value: &str) -> Value {
+    let trimmed = value.trim();
+    match kind {
+        ValueKind::Int => trimmed.parse::<i64>().ok().map(Value::Integer),
+        ValueKind::Float => trimmed.parse::<f64>().ok().map(Value::Real),
+        ValueKind::Bool => parse_bool(trimmed).map(|b| Value::Integer(b as i64)),
+        ValueKind::String => None,
+    }
+    .unwrap_or_else(|| Value::Text(value.to_string()))
+}
+
+/// 把导出到目标的所有表写入一个SQLite数据库, 每张表一个同名的表(保持目录结构时带子目录),
+/// 第一列为主键; 主键按类型转换后重复的行只保留第一行, 写入失败的行跳过, 都报告错误
+pub fn write_database(
+    tables: &[&SheetTable],
+    target: &ExportTarget,
+    dir: &path::Path,
+    errors: &mut Vec<String>,
+) -> io::Result<()> {
+    let path = dir.join(SQLITE_FILE);
+    if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    let mut connection = Connection::open(&path).map_err(sqlite_error)?;
+    let transaction = connection.transaction().map_err(sqlite_error)?;
+    transaction
+        .execute_batch(&format!(
+            "CREATE TABLE {} (\"table\" TEXT, \"column\" TEXT, \"type\" TEXT, \"comment\" TEXT);",
+            COLUMNS_TABLE
+        ))
+        .map_err(sqlite_error)?;
+
+    for table in tables {
+        let name = table_id(table);
+        let columns = target_columns(table, target);
+        if columns.is_empty() {
+            continue;
+        }
+        let kinds: Vec<ValueKind> = columns
+            .iter()
+            .map(|&i| table.columns[i].column_type.kind())
+            .collect();
+        let definitions: Vec<String> = columns
+            .iter()
+            .zip(&kinds)
+            .enumerate()
+            .map(|(position, (&i, &kind))| {
+                format!(
+                    "{} {}{}",
+                    quote(&table.columns[i].name),
+                    column_affinity(kind),
+                    if position == 0 { " PRIMARY KEY" } else { "" }
+                )
+            })
+            .collect();
+        // WITHOUT ROWID 表的 INTEGER 主键不是rowid别名, 无法转换为整数的主键按文本存储
+        transaction
+            .execute_batch(&format!(
+                "CREATE TABLE {} ({}) WITHOUT ROWID;",
+                quote(&name),
+                definitions.join(", ")
+            ))
+            .map_err(sqlite_error)?;
+
+        for &i in &columns {
+            let column = &table.columns[i];
+            // 第一列的注释是A1表选项
+            let comment = if i == 0 { "" } else { column.comment.as_str() };
+            let type_name = match target.header {
+                HeaderStyle::Server => &column.column_type.server,
+                HeaderStyle::Client => &column.column_type.client,
+            };
+            transaction
+                .execute(
+                    &format!("INSERT INTO {} VALUES (?1, ?2, ?3, ?4)", COLUMNS_TABLE),
+                    params![name, column.name, type_name, comment],
+                )
+                .map_err(sqlite_error)?;
+        }
+
+        let placeholders: Vec<String> = (1..=columns.len()).map(|n| format!("?{}", n)).collect();
+        let mut insert = transaction
+            .prepare(&format!(
+                "INSERT INTO {} VALUES ({})",
+                quote(&name),
+                placeholders.join(", ")
+            ))
+            .map_err(sqlite_error)?;
+        let mut keys = HashSet::new();
+        for row in &table.rows {
+            let values: Vec<Value> = columns
+                .iter()
+                .zip(&kinds)
+                .map(|(&i, &kind)| sql_value(kind, &row.values[i]))
+                .collect();
+            // Value 没有实现 Hash, 用调试格式区分类型和值, 如 " 1" 和 "1" 转换后都是 Integer(1)
+            if !keys.insert(format!("{:?}", values[0])) {
+                errors.push(format!(
+                    "<<{}>> 第{}行: 主键 {} 重复, SQLite中只保留第一行",
+                    table.source, row.line, row.values[columns[0]]
+                ));
+                continue;
+            }
+            if let Err(e) = insert.execute(rusqlite::params_from_iter(values)) {
+                errors.push(format!(
+                    "<<{}>> 第{}行: 写入SQLite失败: {}",
+                    table.source, row.line, e
+                ));
+            }
+        }
+    }
+    transaction.commit().map_err(sqlite_error)
+}
@@ -1,8 +1,17 @@
-use crate::file_utils::{check_dir_path, check_file_exist, copy_dir_files_no_subdir, copy_file};
+use crate::binary_table::{BINARY_TABLE_EXTENSION, TABLE_READER_TS_FILE};
+use crate::export_manifest::MANIFEST_FILE;
+use crate::export_rules::ExportRules;
+use crate::file_utils::{check_dir_path, check_file_exist, copy_dir_files, copy_file};
+use crate::table_chunks::CHUNK_INDEX_SUFFIX;
+use crate::translation::LOCALES_DIR;
+use crate::translation_keys::TRANSLATION_KEYS_TS_FILE;
 use eframe::egui;
 use std::path::PathBuf;
 use std::time::Duration;
 
+/// 同步到客户端工程的导出目标, 输出目录见 `ExportRules::target_dir`
+const CLIENT_TARGET: &str = "client";
+
 pub fn sync_client_ui(app: &mut crate::App, ui: &mut egui::Ui) {
     ui.label("同步客户端");
     ui.add_space(5.0);
@@ -12,7 +21,15 @@ pub fn sync_client_ui(app: &mut crate::App, ui: &mut egui::Ui) {
             .add(egui::Button::new("同步客户端CSV&ts").min_size(egui::vec2(150.0, 30.0)))
             .clicked()
         {
-            let csv_src_dir = PathBuf::from(&app.output_dir).join("client");
+            let csv_src_dir = match ExportRules::load(&app.excel_dir) {
+                Ok(rules) => rules.target_dir(&app.output_dir, CLIENT_TARGET),
+                Err(err) => {
+                    app.toasts
+                        .error(err.to_string())
+                        .duration(Duration::from_secs(5).into());
+                    return;
+                }
+            };
             if let Err(err) = check_dir_path(&csv_src_dir) {
                 app.toasts
                     .error(err.to_string())
@@ -20,11 +37,42 @@ pub fn sync_client_ui(app: &mut crate::App, ui: &mut egui::Ui) {
                 return;
             }
 
-            let ts_src_file = PathBuf::from(&csv_src_dir).join("CXTranslationText.ts");
-            if check_file_exist(&ts_src_file) {
-                let ts_dst_file = PathBuf::from(&app.client_dir)
-                    .join("assets/scripts/framework/cx18n/CXTranslationText.ts");
-                if let Err(err) = copy_file(&ts_src_file, &ts_dst_file) {
+            for ts_file in ["CXTranslationText.ts", TRANSLATION_KEYS_TS_FILE] {
+                let ts_src_file = PathBuf::from(&csv_src_dir).join(ts_file);
+                if check_file_exist(&ts_src_file) {
+                    let ts_dst_file = PathBuf::from(&app.client_dir)
+                        .join("assets/scripts/framework/cx18n")
+                        .join(ts_file);
+                    if let Err(err) = copy_file(&ts_src_file, &ts_dst_file) {
+                        app.toasts
+                            .error(format!("复制ts文件失败: {}", err))
+                            .duration(Duration::from_secs(5).into());
+                        return;
+                    }
+                }
+            }
+
+            // 按语言拆分的翻译文件
+            let locales_src_dir = csv_src_dir.join(LOCALES_DIR);
+            if locales_src_dir.is_dir() {
+                let locales_dst_dir = PathBuf::from(&app.client_dir)
+                    .join("assets/scripts/framework/cx18n")
+                    .join(LOCALES_DIR);
+                if let Err(err) = copy_dir_files(&locales_src_dir, &locales_dst_dir, |_| false) {
+                    app.toasts
+                        .error(format!("复制多语言文件失败: {}", err))
+                        .duration(Duration::from_secs(5).into());
+                    return;
+                }
+            }
+
+            // 二进制表的读取器
+            let reader_src_file = csv_src_dir.join(TABLE_READER_TS_FILE);
+            if check_file_exist(&reader_src_file) {
+                let reader_dst_file = PathBuf::from(&app.client_dir)
+                    .join("assets/scripts/framework/cxtable")
+                    .join(TABLE_READER_TS_FILE);
+                if let Err(err) = copy_file(&reader_src_file, &reader_dst_file) {
                     app.toasts
                         .error(format!("复制ts文件失败: {}", err))
                         .duration(Duration::from_secs(5).into());
@@ -32,9 +80,16 @@ pub fn sync_client_ui(app: &mut crate::App, ui: &mut egui::Ui) {
                 }
             }
 
+            // CSV、二进制表和拆分表的分块索引
             let csv_dst_dir = PathBuf::from(&app.client_dir).join("assets/csv");
-            if let Err(err) = copy_dir_files_no_subdir(&csv_src_dir, &csv_dst_dir, |entry| {
-                entry.path().extension().map_or(true, |ext| ext != "csv")
+            if let Err(err) = copy_dir_files(&csv_src_dir, &csv_dst_dir, |path| {
+                let is_index = path
+                    .to_str()
+                    .is_some_and(|path| path.ends_with(CHUNK_INDEX_SUFFIX));
+                !is_index
+                    && path
+                        .extension()
+                        .is_none_or(|ext| ext != "csv" && ext != BINARY_TABLE_EXTENSION)
             }) {
                 app.toasts
                     .error(format!("复制csv文件失败: {}", err))
@@ -42,6 +97,17 @@ pub fn sync_client_ui(app: &mut crate::App, ui: &mut egui::Ui) {
                 return;
             }
 
+            let manifest_src_file = csv_src_dir.join(MANIFEST_FILE);
+            if check_file_exist(&manifest_src_file) {
+                let manifest_dst_file = csv_dst_dir.join(MANIFEST_FILE);
+                if let Err(err) = copy_file(&manifest_src_file, &manifest_dst_file) {
+                    app.toasts
+                        .error(format!("复制清单文件失败: {}", err))
+                        .duration(Duration::from_secs(5).into());
+                    return;
+                }
+            }
+
             app.toasts
                 .success("同步完成")
                 .duration(Duration::from_secs(5).into());
@@ -1,7 +1,14 @@
 use crate::AppNotice;
+use crate::export_report::last_export_errors;
+use crate::export_rules::ExportRules;
+use crate::export_schema::{SYNC_GATED_TARGET, acknowledge_changes, pending_changes};
 use eframe::egui;
+use std::path::Path;
+use std::time::Duration;
 
 pub const SERVER_MAP: &[(&str, &str)] = &[("olddev", "老服"), ("dev", "dev服"), ("cqdev", "CQ服")];
+/// 上传到服务器的导出目标, 输出目录见 `ExportRules::target_dir`
+const SERVER_TARGET: &str = SYNC_GATED_TARGET;
 
 pub fn sync_server_ui(app: &mut crate::App, ui: &mut egui::Ui) {
     ui.horizontal(|ui| {
@@ -16,19 +23,78 @@ pub fn sync_server_ui(app: &mut crate::App, ui: &mut egui::Ui) {
             );
         }
     });
+
+    // 服务器表结构有未确认的变更时不能同步
+    let output_dir = Path::new(&app.output_dir).to_path_buf();
+    let pending = app
+        .pending_schema_changes
+        .get_or_insert_with(|| {
+            pending_changes(&output_dir)
+                .into_iter()
+                .filter(|change| change.target == SERVER_TARGET)
+                .collect()
+        })
+        .clone();
+    if !pending.is_empty() {
+        ui.colored_label(
+            egui::Color32::RED,
+            "服务器表结构有变更, 可能导致服务器加载失败, 确认后才能同步:",
+        );
+        for change in &pending {
+            ui.colored_label(egui::Color32::YELLOW, change.message());
+        }
+        if ui.button("确认表结构变更").clicked() {
+            match acknowledge_changes(&output_dir, SERVER_TARGET) {
+                Ok(_) => app.pending_schema_changes = None,
+                Err(e) => {
+                    app.toasts
+                        .error(format!("确认表结构变更失败: {}", e))
+                        .duration(Duration::from_secs(5).into());
+                }
+            }
+        }
+    }
+
+    // 上次导出有校验错误或未完成时不能同步
+    let export_errors = app
+        .last_export_errors
+        .get_or_insert_with(|| last_export_errors(&output_dir))
+        .clone();
+    if !export_errors.is_empty() {
+        ui.colored_label(
+            egui::Color32::RED,
+            format!(
+                "上次导出有{}个错误, 修复后重新导出才能同步:",
+                export_errors.len()
+            ),
+        );
+        for error in &export_errors {
+            ui.colored_label(egui::Color32::YELLOW, error);
+        }
+    }
+
     ui.horizontal(|ui| {
         SERVER_MAP.iter().for_each(|(server, label)| {
             if ui
-                .add(
+                .add_enabled(
+                    pending.is_empty() && export_errors.is_empty(),
                     egui::Button::new(format!("[{}] {}", *server, *label))
                         .min_size(egui::vec2(150.0, 30.0)),
                 )
                 .clicked()
             {
-                let output_dir = app.output_dir.clone();
+                let input_dir = match ExportRules::load(&app.excel_dir) {
+                    Ok(rules) => rules.target_dir(&app.output_dir, SERVER_TARGET),
+                    Err(err) => {
+                        app.toasts
+                            .error(err.to_string())
+                            .duration(Duration::from_secs(5).into());
+                        return;
+                    }
+                };
+                let input_dir = input_dir.to_string_lossy().to_string();
                 let server_name = server.to_string();
                 let sender = app.notice_sender.clone().unwrap();
-                let input_dir = format!("{}/server", output_dir);
                 tokio::spawn(async move {
                     match crate::minio_uploader::minio_upload(
                         input_dir,
@@ -0,0 +1,75 @@
+use crate::column_type::ValueKind;
+use crate::xlsx2csv::{SheetRow, SheetTable};
+use serde::Serialize;
+use std::cmp::Ordering;
+
+/// 拆分后的表不再写出整表文件, 改为写出 `表名.index.json` 记录每个分块的主键范围
+pub const CHUNK_INDEX_SUFFIX: &str = ".index.json";
+
+#[derive(Serialize)]
+pub struct ChunkIndex {
+    pub table: String,
+    /// 主键列名
+    pub key: String,
+    pub chunks: Vec<ChunkRange>,
+}
+
+#[derive(Serialize)]
+pub struct ChunkRange {
+    /// 与索引文件在同一目录下的分块文件名
+    pub file: String,
+    /// 分块中最小和最大的主键, 数值主键为数字
+    pub first: serde_json::Value,
+    pub last: serde_json::Value,
+    pub rows: usize,
+}
+
+/// 索引文件名, 相对导出目标目录
+pub fn index_file_name(table: &SheetTable) -> String {
+    format!("{}{}", table.meta.name, CHUNK_INDEX_SUFFIX)
+}
+
+/// 分块文件名, 序号从1开始
+pub fn chunk_file_name(table: &SheetTable, chunk: usize, extension: &str) -> String {
+    format!("{}_{}.{}", table.meta.name, chunk + 1, extension)
+}
+
+/// 按主键比较: 数值主键按数值比较, 字符串按UTF-16编码比较, 与JS的字符串比较一致
+fn compare_keys(numeric: bool, a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) if numeric => a.total_cmp(&b),
+        _ => a.encode_utf16().cmp(b.encode_utf16()),
+    }
+}
+
+/// 行按主键排序后平均分成最多 count 块, 每块是一段连续的主键范围
+pub fn split_rows(table: &SheetTable, count: usize) -> Vec<Vec<&SheetRow>> {
+    let numeric = table
+        .columns
+        .first()
+        .is_some_and(|c| matches!(c.column_type.kind(), ValueKind::Int | ValueKind::Float));
+    let mut rows: Vec<&SheetRow> = table.rows.iter().collect();
+    rows.sort_by(|a, b| compare_keys(numeric, &a.values[0], &b.values[0]));
+
+    let size = rows.len().div_ceil(count.max(1)).max(1);
+    rows.chunks(size).map(|chunk| chunk.to_vec()).collect()
+}
+
+/// 分块的索引, chunks 与 `split_rows` 的结果一一对应
+pub fn chunk_index(table: &SheetTable, chunks: &[Vec<&SheetRow>], extension: &str) -> ChunkIndex {
+    let key = |row: &SheetRow| table.columns[0].column_type.json_value(&row.values[0]);
+    ChunkIndex {
+        table: table.meta.name.clone(),
+        key: table.columns[0].name.clone(),
+        chunks: chunks
+            .iter()
+            .enumerate()
+            .map(|(i, rows)| ChunkRange {
+                file: chunk_file_name(table, i, extension),
+                first: key(rows[0]),
+                last: key(rows[rows.len() - 1]),
+                rows: rows.len(),
+            })
+            .collect(),
+    }
+}
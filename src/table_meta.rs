@@ -0,0 +1,195 @@
+use serde::Deserialize;
+
+/// 工作表A1单元格中的表选项
+///
+/// 兼容旧的按位置格式 `名称#服务器(1/0)#客户端(1/0)#行数限制`,
+/// 也支持 `Item#server#client#limit=200#format=json#group=battle#chunks=4#width_column=MAX_WIDTH` 这样的键值格式,
+/// 其中不带`=`的字段是导出目标名; 没有标记导出目标时导出到所有默认目标
+#[derive(Clone, Debug)]
+pub struct TableMeta {
+    pub name: String,
+    pub targets: Option<Vec<String>>,
+    pub line_limit: usize,
+    /// 覆盖导出目标配置的格式
+    pub format: Option<TableFormat>,
+    pub group: Option<String>,
+    /// 客户端表头的导出目标中按主键范围拆分的块数, 0或1表示不拆分, 见 `table_chunks`
+    pub chunks: usize,
+    /// 行显示宽度列的字段名, 该列不导出, 限制该行所有字符串列的显示宽度, 见 `text_width::check_table`
+    pub width_column: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    Json,
+    /// 返回以主键为键的表的Lua模块, 见 `lua_table::table_lua`
+    Lua,
+}
+
+impl TableFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Json => "json",
+            TableFormat::Lua => "lua",
+        }
+    }
+}
+
+impl TableMeta {
+    /// 解析A1单元格, 返回表选项和无法识别的选项警告
+    pub fn parse(cell: &str) -> (Self, Vec<String>) {
+        let mut fields = cell.split('#');
+        let mut meta = Self {
+            name: fields.next().unwrap_or_default().trim().to_string(),
+            targets: None,
+            line_limit: 0,
+            format: None,
+            group: None,
+            chunks: 0,
+            width_column: None,
+        };
+        let mut warnings = Vec::new();
+        let mut targets = Vec::new();
+        let mut targets_given = false;
+        // 前两个字段是 1/0 开关时按旧格式解析, 第3个字段为行数限制
+        let legacy = fields
+            .clone()
+            .take(2)
+            .all(|f| matches!(f.trim(), "0" | "1" | ""));
+
+        for (position, field) in fields.enumerate().map(|(i, f)| (i + 1, f.trim())) {
+            if let Some((key, value)) = field.split_once('=') {
+                let value = value.trim();
+                match key.trim() {
+                    "limit" => match value.parse() {
+                        Ok(limit) => meta.line_limit = limit,
+                        Err(_) => warnings.push(format!("行数限制不是数字: {}", field)),
+                    },
+                    "format" => match value {
+                        "csv" => meta.format = Some(TableFormat::Csv),
+                        "json" => meta.format = Some(TableFormat::Json),
+                        "lua" => meta.format = Some(TableFormat::Lua),
+                        _ => warnings.push(format!("未知的导出格式: {}", field)),
+                    },
+                    "group" => meta.group = Some(value.to_string()),
+                    "chunks" => match value.parse() {
+                        Ok(chunks) => meta.chunks = chunks,
+                        Err(_) => warnings.push(format!("分块数不是数字: {}", field)),
+                    },
+                    "width_column" if !value.is_empty() => {
+                        meta.width_column = Some(value.to_string())
+                    }
+                    _ => warnings.push(format!("未知选项: {}", field)),
+                }
+                continue;
+            }
+
+            // 旧格式: 第1、2个字段为服务器、客户端开关, 第3个字段为行数限制
+            match (position, field) {
+                (1, "1") => targets.push("server".to_string()),
+                (2, "1") => targets.push("client".to_string()),
+                (1 | 2, "0" | "") => {}
+                (_, "") => continue,
+                (3, limit) if legacy => {
+                    match limit.parse() {
+                        Ok(limit) => meta.line_limit = limit,
+                        Err(_) => warnings.push(format!("行数限制不是数字: {}", field)),
+                    }
+                    continue;
+                }
+                (_, target) => targets.push(target.to_string()),
+            }
+            targets_given = true;
+        }
+
+        if targets_given {
+            meta.targets = Some(targets);
+        }
+        (meta, warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn targets(meta: &TableMeta) -> Option<Vec<&str>> {
+        meta.targets
+            .as_ref()
+            .map(|targets| targets.iter().map(String::as_str).collect())
+    }
+
+    #[test]
+    fn name_only_exports_to_default_targets() {
+        let (meta, warnings) = TableMeta::parse("Item");
+        assert_eq!(meta.name, "Item");
+        assert_eq!(targets(&meta), None);
+        assert_eq!(meta.line_limit, 0);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn legacy_flags_and_limit() {
+        let (meta, warnings) = TableMeta::parse("Item#1#0");
+        assert_eq!(targets(&meta), Some(vec!["server"]));
+        assert!(warnings.is_empty());
+
+        let (meta, warnings) = TableMeta::parse("Item#0#1#200");
+        assert_eq!(targets(&meta), Some(vec!["client"]));
+        assert_eq!(meta.line_limit, 200);
+        assert!(warnings.is_empty());
+
+        let (meta, warnings) = TableMeta::parse("Item#1#1#");
+        assert_eq!(targets(&meta), Some(vec!["server", "client"]));
+        assert_eq!(meta.line_limit, 0);
+        assert!(warnings.is_empty());
+
+        let (meta, warnings) = TableMeta::parse("Item#0#0");
+        assert_eq!(targets(&meta), Some(vec![]));
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn legacy_non_numeric_limit_warns() {
+        let (meta, warnings) = TableMeta::parse("Item#1#1#abc");
+        assert_eq!(targets(&meta), Some(vec!["server", "client"]));
+        assert_eq!(meta.line_limit, 0);
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn target_names_and_options() {
+        let (meta, warnings) = TableMeta::parse(
+            "Item#server#gm-web#limit=50#format=json#group=battle#chunks=4#width_column=MAX_WIDTH",
+        );
+        assert_eq!(targets(&meta), Some(vec!["server", "gm-web"]));
+        assert_eq!(meta.line_limit, 50);
+        assert_eq!(meta.format, Some(TableFormat::Json));
+        assert_eq!(meta.group.as_deref(), Some("battle"));
+        assert_eq!(meta.chunks, 4);
+        assert_eq!(meta.width_column.as_deref(), Some("MAX_WIDTH"));
+        assert!(warnings.is_empty());
+
+        // 不是旧格式时第3个字段是目标名
+        let (meta, _) = TableMeta::parse("Item#server#client#gm");
+        assert_eq!(targets(&meta), Some(vec!["server", "client", "gm"]));
+
+        let (meta, warnings) = TableMeta::parse("Item#limit=200");
+        assert_eq!(targets(&meta), None);
+        assert_eq!(meta.line_limit, 200);
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn invalid_options_warn() {
+        let (meta, warnings) = TableMeta::parse("Item#limit=x#format=xml#chunks=y#foo=bar");
+        assert_eq!(targets(&meta), None);
+        assert_eq!(meta.line_limit, 0);
+        assert_eq!(meta.format, None);
+        assert_eq!(warnings.len(), 4);
+    }
+}
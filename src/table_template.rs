@@ -0,0 +1,392 @@
+use crate::column_type::{ColumnType, ValueKind};
+use eframe::egui;
+use rust_xlsxwriter::{DataValidation, Format, Workbook, XlsxError};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::io::{self, Error};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 数据行的下拉列表覆盖到工作表的最后一行
+const LAST_ROW: u32 = 1_048_575;
+
+/// 新建表的结构定义, 从json文件读取
+///
+/// ```json
+/// {
+///   "name": "Item",
+///   "targets": ["server", "client"],
+///   "group": "battle",
+///   "columns": [
+///     { "name": "id", "type": "int", "description": "编号" },
+///     { "name": "quality", "type": "int", "client_type": "number", "description": "品质", "values": ["1", "2", "3"] },
+///     { "name": "tradable", "type": "bool", "description": "可交易", "default": "true" },
+///     { "name": "icon", "type": "asset:spriteFrame", "description": "图标", "required": true, "targets": ["client"] }
+///   ]
+/// }
+/// ```
+#[derive(Deserialize)]
+pub struct TableSchema {
+    pub name: String,
+    /// 相对Excel目录的工作簿路径, 为空时为 `表名.xlsx`
+    #[serde(default)]
+    pub file: Option<String>,
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// 为空时导出到所有默认目标
+    #[serde(default)]
+    pub targets: Vec<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    pub columns: Vec<ColumnSchema>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct ColumnSchema {
+    pub name: String,
+    /// 服务器类型
+    #[serde(rename = "type")]
+    pub column_type: String,
+    /// 客户端类型, 为空时与服务器类型相同
+    pub client_type: Option<String>,
+    /// 写入注释行, 为空时使用字段名; 注释为空的列不会导出
+    pub description: String,
+    pub default: Option<String>,
+    pub required: bool,
+    pub targets: Vec<String>,
+    pub width: Option<usize>,
+    /// 可选值, 生成下拉列表; bool列总是生成 true/false 下拉列表
+    pub values: Vec<String>,
+}
+
+impl TableSchema {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json_string = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json_string).map_err(|e| {
+            Error::new(
+                io::ErrorKind::Other,
+                format!("解析{}失败: {}", path.display(), e),
+            )
+        })
+    }
+
+    /// A1单元格的表选项, 见 `TableMeta::parse`
+    pub fn meta_cell(&self) -> String {
+        let mut fields = vec![self.name.clone()];
+        fields.extend(self.targets.iter().cloned());
+        if let Some(group) = &self.group {
+            fields.push(format!("group={}", group));
+        }
+        fields.join("#")
+    }
+
+    /// 检查结构定义: 写入A1和类型行的名称不能包含分隔符, 工作簿路径必须在Excel目录下
+    fn check(&self, excel_dir: &Path) -> io::Result<()> {
+        let error = |message: String| Error::new(io::ErrorKind::Other, message);
+        // A1按`#`分隔表选项, 类型行按`|`分隔选项、按`#`分隔服务器和客户端类型、按`+`分隔导出目标
+        let invalid = |value: &str, separators: &[char]| {
+            value.trim().is_empty() || value.contains(separators)
+        };
+        if excel_dir.as_os_str().is_empty() {
+            return Err(error("未设置Excel目录".to_string()));
+        }
+        if invalid(&self.name, &['#', '|']) {
+            return Err(error(format!("表名 {:?} 为空或包含#、|", self.name)));
+        }
+        if let Some(file) = &self.file {
+            let path = Path::new(file);
+            let relative = !file.trim().is_empty()
+                && !path.is_absolute()
+                && !file.starts_with(['/', '\\'])
+                && !file.contains(':')
+                && path
+                    .components()
+                    .all(|c| matches!(c, std::path::Component::Normal(_)));
+            if !relative {
+                return Err(error(format!(
+                    "工作簿路径 {:?} 必须是Excel目录下的相对路径, 不能包含.或..",
+                    file
+                )));
+            }
+        }
+        for target in &self.targets {
+            if invalid(target, &['#', '|', '=']) {
+                return Err(error(format!(
+                    "表 {} 的导出目标 {:?} 为空或包含#、|、=",
+                    self.name, target
+                )));
+            }
+        }
+        if self.group.as_deref().is_some_and(|g| invalid(g, &['#'])) {
+            return Err(error(format!("表 {} 的分组为空或包含#", self.name)));
+        }
+        if self.columns.is_empty() {
+            return Err(error(format!("表 {} 没有列", self.name)));
+        }
+        let mut names = HashSet::new();
+        for column in &self.columns {
+            if invalid(&column.name, &['#', '|'])
+                || invalid(&column.column_type, &['#', '|'])
+                || column
+                    .client_type
+                    .as_deref()
+                    .is_some_and(|t| invalid(t, &['#', '|']))
+            {
+                return Err(error(format!(
+                    "表 {} 有字段名或类型为空或包含#、|的列: {:?}",
+                    self.name, column.name
+                )));
+            }
+            if !names.insert(column.name.as_str()) {
+                return Err(error(format!(
+                    "表 {} 的字段名 {} 重复",
+                    self.name, column.name
+                )));
+            }
+            // 默认值在类型行的`|`之后, 可以包含#(例如颜色值)
+            if column.default.as_deref().is_some_and(|d| d.contains('|')) {
+                return Err(error(format!(
+                    "表 {} 字段 {} 的默认值不能包含|",
+                    self.name, column.name
+                )));
+            }
+            if column
+                .targets
+                .iter()
+                .any(|target| invalid(target, &['#', '|', '+']))
+            {
+                return Err(error(format!(
+                    "表 {} 字段 {} 的导出目标为空或包含#、|、+",
+                    self.name, column.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ColumnSchema {
+    /// 类型行单元格, 见 `ColumnType::parse`
+    pub fn type_cell(&self) -> String {
+        let mut cell = self.column_type.clone();
+        if let Some(client_type) = self
+            .client_type
+            .as_ref()
+            .filter(|t| **t != self.column_type)
+        {
+            cell = format!("{}#{}", cell, client_type);
+        }
+        if let Some(default) = &self.default {
+            cell.push_str(&format!("|default={}", default));
+        }
+        if self.required {
+            cell.push_str("|required");
+        }
+        if !self.targets.is_empty() {
+            cell.push_str(&format!("|targets={}", self.targets.join("+")));
+        }
+        if let Some(width) = self.width {
+            cell.push_str(&format!("|width={}", width));
+        }
+        cell
+    }
+
+    /// 下拉列表的选项
+    fn dropdown_values(&self) -> Vec<String> {
+        if !self.values.is_empty() {
+            return self.values.clone();
+        }
+        match ColumnType::parse(&self.type_cell()).kind() {
+            ValueKind::Bool => vec!["true".to_string(), "false".to_string()],
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn xlsx_error(e: XlsxError) -> Error {
+    Error::new(io::ErrorKind::Other, format!("生成工作簿失败: {}", e))
+}
+
+/// 按结构定义在Excel目录下新建工作簿, 写好A1表选项、注释行、字段名行和类型行,
+/// 有可选值的列和bool列在数据行加下拉列表; 工作簿已存在时报错, 返回新建的工作簿路径
+pub fn create_template(schema: &TableSchema, excel_dir: &Path) -> io::Result<PathBuf> {
+    schema.check(excel_dir)?;
+    let file = schema
+        .file
+        .clone()
+        .unwrap_or_else(|| format!("{}.xlsx", schema.name));
+    let path = excel_dir.join(file);
+    if path.exists() {
+        return Err(Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} 已存在", path.display()),
+        ));
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    worksheet
+        .set_name(schema.sheet.as_deref().unwrap_or("Sheet1"))
+        .map_err(xlsx_error)?;
+    let bold = Format::new().set_bold();
+    for (col, column) in schema.columns.iter().enumerate() {
+        let col = col as u16;
+        let comment = if col == 0 {
+            schema.meta_cell()
+        } else if column.description.trim().is_empty() {
+            column.name.clone()
+        } else {
+            column.description.clone()
+        };
+        worksheet
+            .write_string(0, col, comment)
+            .map_err(xlsx_error)?;
+        worksheet
+            .write_string_with_format(1, col, &column.name, &bold)
+            .map_err(xlsx_error)?;
+        worksheet
+            .write_string(2, col, column.type_cell())
+            .map_err(xlsx_error)?;
+        let width = column.name.len().max(column.type_cell().len()).max(12);
+        worksheet
+            .set_column_width(col, width as f64)
+            .map_err(xlsx_error)?;
+
+        let values = column.dropdown_values();
+        if !values.is_empty() {
+            let validation = DataValidation::new()
+                .allow_list_strings(&values)
+                .and_then(|validation| {
+                    validation.set_error_message(format!("{} 只能从下拉列表中选择", column.name))
+                })
+                .map_err(|e| {
+                    Error::new(
+                        io::ErrorKind::Other,
+                        format!("{} 的下拉列表无效: {}", column.name, e),
+                    )
+                })?;
+            worksheet
+                .add_data_validation(3, col, LAST_ROW, col, &validation)
+                .map_err(xlsx_error)?;
+        }
+    }
+    worksheet.set_freeze_panes(3, 1).map_err(xlsx_error)?;
+    workbook.save(&path).map_err(xlsx_error)?;
+    Ok(path)
+}
+
+pub fn table_template_ui(app: &mut crate::App, ui: &mut egui::Ui) {
+    ui.label("新建表");
+    ui.add_space(5.0);
+
+    ui.horizontal(|ui| {
+        if ui
+            .add(egui::Button::new("从结构定义创建表").min_size(egui::vec2(150.0, 30.0)))
+            .clicked()
+        {
+            let Some(files) = rfd::FileDialog::new()
+                .add_filter("表结构定义", &["json"])
+                .pick_files()
+            else {
+                return;
+            };
+            for file in files {
+                let result = TableSchema::load(&file)
+                    .and_then(|schema| create_template(&schema, Path::new(&app.excel_dir)));
+                match result {
+                    Ok(path) => {
+                        app.toasts
+                            .success(format!("已创建 {}", path.display()))
+                            .duration(Duration::from_secs(5).into());
+                    }
+                    Err(err) => {
+                        app.toasts
+                            .error(format!("创建表失败: {}", err))
+                            .duration(Duration::from_secs(5).into());
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(schema: serde_json::Value) -> io::Result<()> {
+        let schema: TableSchema = serde_json::from_value(schema).unwrap();
+        schema.check(Path::new("excel"))
+    }
+
+    fn item(column: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "name": "Item",
+            "columns": [{ "name": "id", "type": "int" }, column]
+        })
+    }
+
+    #[test]
+    fn valid_schema() {
+        let schema = serde_json::json!({
+            "name": "Item",
+            "file": "battle/Item.xlsx",
+            "targets": ["server", "gm-web"],
+            "columns": [
+                { "name": "id", "type": "int" },
+                { "name": "color", "type": "string", "default": "#ff0000", "targets": ["client"] }
+            ]
+        });
+        assert!(check(schema).is_ok());
+    }
+
+    #[test]
+    fn rejects_paths_outside_excel_dir() {
+        let schema: TableSchema =
+            serde_json::from_value(item(serde_json::json!({ "name": "n", "type": "string" })))
+                .unwrap();
+        assert!(schema.check(Path::new("")).is_err());
+        for file in [
+            "/tmp/Item.xlsx",
+            "../Item.xlsx",
+            "a/../../Item.xlsx",
+            "./Item.xlsx",
+            "C:\\Item.xlsx",
+            "",
+        ] {
+            let mut schema = item(serde_json::json!({ "name": "n", "type": "string" }));
+            schema["file"] = file.into();
+            assert!(check(schema).is_err(), "{}", file);
+        }
+    }
+
+    #[test]
+    fn rejects_separators() {
+        let mut schema = item(serde_json::json!({ "name": "n", "type": "string" }));
+        schema["name"] = "Item#1".into();
+        assert!(check(schema).is_err());
+
+        let mut schema = item(serde_json::json!({ "name": "n", "type": "string" }));
+        schema["targets"] = serde_json::json!(["server#client"]);
+        assert!(check(schema).is_err());
+
+        let mut schema = item(serde_json::json!({ "name": "n", "type": "string" }));
+        schema["targets"] = serde_json::json!(["limit=5"]);
+        assert!(check(schema).is_err());
+
+        for column in [
+            serde_json::json!({ "name": "a|b", "type": "string" }),
+            serde_json::json!({ "name": "a#b", "type": "string" }),
+            serde_json::json!({ "name": "n", "type": "int|required" }),
+            serde_json::json!({ "name": "n", "type": "int", "client_type": "a#b" }),
+            serde_json::json!({ "name": "n", "type": "string", "default": "a|required" }),
+            serde_json::json!({ "name": "n", "type": "string", "targets": ["a+b"] }),
+        ] {
+            assert!(check(item(column.clone())).is_err(), "{}", column);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::column_type::ValueKind;
+use crate::export_rules::ExportRules;
+use crate::translation::{RichText, rich_text_segments};
+use crate::xlsx2csv::{SheetTable, cell_name};
+
+/// 文本的显示宽度: 去掉富文本标签后, 中日韩文字和全角字符按2计算, 零宽字符和组合字符按0计算,
+/// 其他字符按1计算; 多行文本取最宽的一行, 换行符和单元格中手写的 `\n` 都视为换行
+pub fn display_width(text: &str) -> usize {
+    strip_rich_text_tags(text)
+        .replace("\\n", "\n")
+        .lines()
+        .map(|line| line.chars().map(char_width).sum())
+        .max()
+        .unwrap_or(0)
+}
+
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036f | 0x200b..=0x200f | 0x2060..=0x2064 | 0xfe00..=0xfe0f | 0xfeff => 0,
+        0x1100..=0x115f
+        | 0x2e80..=0x303e
+        | 0x3040..=0xa4cf
+        | 0xac00..=0xd7a3
+        | 0xf900..=0xfaff
+        | 0xfe30..=0xfe4f
+        | 0xff00..=0xff60
+        | 0xffe0..=0xffe6
+        | 0x1f300..=0x1f64f
+        | 0x1f900..=0x1f9ff
+        | 0x20000..=0x3fffd => 2,
+        _ => 1,
+    }
+}
+
+/// 去掉 `<color=#ff0000>`、`</b>`、`<br/>` 这样的富文本标签, 缺少 `>` 的标签按普通文本显示
+fn strip_rich_text_tags(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for segment in rich_text_segments(text) {
+        match segment {
+            RichText::Text(text) => result.push_str(text),
+            RichText::Tag(_) => {}
+            RichText::UnclosedTag(rest) => {
+                result.push('<');
+                result.push_str(rest);
+            }
+        }
+    }
+    result
+}
+
+/// 检查字符串列的显示宽度: 列宽度取类型行的 `width=` 或规则文件中的 width,
+/// 行宽度取A1中 `width_column=` 指定的列, 都声明时取较小值
+pub fn check_table(table: &SheetTable, rules: &ExportRules, warnings: &mut Vec<String>) {
+    let column_widths: Vec<Option<usize>> = table
+        .columns
+        .iter()
+        .map(|column| {
+            if column.column_type.kind() != ValueKind::String {
+                return None;
+            }
+            column.column_type.width.or_else(|| {
+                rules
+                    .column(&table.meta.name, &column.name)
+                    .and_then(|rule| rule.width)
+            })
+        })
+        .collect();
+    let is_string_column = |i: usize| table.columns[i].column_type.kind() == ValueKind::String;
+
+    for row in &table.rows {
+        for (i, value) in row.values.iter().enumerate().skip(1) {
+            if !is_string_column(i) {
+                continue;
+            }
+            let Some(limit) = [column_widths[i], row.max_width]
+                .into_iter()
+                .flatten()
+                .min()
+            else {
+                continue;
+            };
+            let width = display_width(value);
+            if width > limit {
+                let column = &table.columns[i];
+                warnings.push(format!(
+                    "<<{}>> {} {}: 显示宽度{}超过限制{} ({})",
+                    table.source,
+                    cell_name(column.cell_index, row.line),
+                    column.name,
+                    width,
+                    limit,
+                    value
+                ));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widths() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("中文ab"), 6);
+        assert_eq!(display_width("<color=#ff0000>中</color><br/>x"), 3);
+        assert_eq!(display_width("长长\\nab"), 4);
+        assert_eq!(display_width("1<2"), 3);
+        // 缺少 '>' 的标签按文本计算, 富文本检查会报告这个错误
+        assert_eq!(display_width("x<b"), 3);
+    }
+}
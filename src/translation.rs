@@ -0,0 +1,511 @@
+use crate::file_utils::{read_json_or_default, sha256_hex};
+use crate::xlsx2csv::{SheetTable, escape_text};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io, path};
+
+/// 多语言表名, 除第一列(key)外每一列是一种语言
+pub const LANGUAGE_TABLE: &str = "Language";
+/// 按语言拆分的翻译文件所在的子目录
+pub const LOCALES_DIR: &str = "locales";
+/// 输出目录下记录上次导出时各翻译对应的源文本哈希, 用于发现源文本修改后未更新的翻译
+pub const TRANSLATION_STATE_FILE: &str = ".translation_state.json";
+
+#[derive(Clone)]
+pub struct TranslationIssue {
+    pub key: String,
+    pub locale: String,
+    pub kind: TranslationIssueKind,
+    pub detail: String,
+    pub source: String,
+    pub text: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TranslationIssueKind {
+    Missing,
+    SameAsSource,
+    Stale,
+    PlaceholderMismatch,
+    UnbalancedTag,
+    /// 客户端脚本中引用了但Language表中没有的key
+    UnknownKey,
+    /// Language表中没有被客户端脚本引用的key
+    UnusedKey,
+}
+
+impl TranslationIssueKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            TranslationIssueKind::Missing => "缺少翻译",
+            TranslationIssueKind::SameAsSource => "与源文本相同",
+            TranslationIssueKind::Stale => "源文本已修改",
+            TranslationIssueKind::PlaceholderMismatch => "占位符不一致",
+            TranslationIssueKind::UnbalancedTag => "富文本标签不匹配",
+            TranslationIssueKind::UnknownKey => "缺少key",
+            TranslationIssueKind::UnusedKey => "未使用的key",
+        }
+    }
+}
+
+/// key -> 语言 -> 翻译状态
+type TranslationState = BTreeMap<String, BTreeMap<String, LocaleState>>;
+
+#[derive(Clone, Serialize, Deserialize)]
+struct LocaleState {
+    /// 翻译文本的哈希
+    text: String,
+    /// 翻译文本最后一次变化时源文本的哈希
+    source: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LocaleFormat {
+    Ts,
+    Json,
+}
+
+/// 语言列: 字段名行中除第一列(key)外的所有列, 返回列下标和语言名
+pub fn locale_columns(table: &SheetTable) -> impl Iterator<Item = (usize, &str)> {
+    table
+        .columns
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, column)| (i, column.name.trim()))
+}
+
+/// 源文本所在的语言列, 未配置源语言时取第一个语言列
+pub fn source_column<'a>(
+    table: &'a SheetTable,
+    source_locale: Option<&str>,
+) -> Option<(usize, &'a str)> {
+    let mut locales = locale_columns(table);
+    match source_locale {
+        Some(name) => locales.find(|(_, locale)| *locale == name),
+        None => locales.next(),
+    }
+}
+
+/// Language表额外导出客户端使用的CXTranslationText.ts
+pub fn write_translation_ts(table: &SheetTable, dir: &path::Path) -> io::Result<()> {
+    if table.rows.is_empty() {
+        return Ok(());
+    }
+    let mut ts_text = Vec::new();
+    for row in &table.rows {
+        let mapping: serde_json::Map<_, _> = table
+            .columns
+            .iter()
+            .zip(&row.values)
+            .skip(1)
+            .map(|(column, value)| (column.name.clone(), escape_text(value).into()))
+            .collect();
+        let json_str = serde_json::to_string(&mapping)?.replace("\\\\n", "\\n");
+        ts_text.push(format!(
+            "\"{}\": {},",
+            escape_text(&row.values[0]),
+            json_str
+        ));
+    }
+    let ts_content = format!(
+        "let CXTranslationText: Record<string, Record<string, string>> = {{\n{}\n}};\nexport {{ CXTranslationText }};\n",
+        ts_text.join("\n")
+    );
+    fs::write(dir.join("CXTranslationText.ts"), ts_content)
+}
+
+/// 每种语言单独导出一个文件, 并导出列出所有语言的索引, 客户端只需加载当前语言;
+/// 文本按原样写入, 不做CSV的逗号和换行转义
+pub fn write_locale_files(
+    table: &SheetTable,
+    dir: &path::Path,
+    format: LocaleFormat,
+) -> io::Result<()> {
+    let dir = dir.join(LOCALES_DIR);
+    fs::create_dir_all(&dir)?;
+
+    let mut index = serde_json::Map::new();
+    for (column_index, locale) in locale_columns(table) {
+        let texts: serde_json::Map<_, _> = table
+            .rows
+            .iter()
+            .map(|row| {
+                (
+                    row.values[0].clone(),
+                    row.values[column_index].clone().into(),
+                )
+            })
+            .collect();
+        match format {
+            LocaleFormat::Ts => {
+                let name = locale_module_name(locale);
+                let ts_content = format!(
+                    "const {name}: Record<string, string> = {};\nexport default {name};\n",
+                    serde_json::to_string_pretty(&texts)?
+                );
+                fs::write(dir.join(format!("{}.ts", name)), ts_content)?;
+                index.insert(locale.to_string(), name.into());
+            }
+            LocaleFormat::Json => {
+                let file_name = format!("{}.json", locale);
+                fs::write(dir.join(&file_name), serde_json::to_string_pretty(&texts)?)?;
+                index.insert(locale.to_string(), file_name.into());
+            }
+        }
+    }
+
+    match format {
+        LocaleFormat::Ts => {
+            let ts_content = format!(
+                "/** 语言 -> 翻译模块名 */\nexport const CXTranslationLocales: Record<string, string> = {};\n",
+                serde_json::to_string_pretty(&index)?
+            );
+            fs::write(dir.join("index.ts"), ts_content)
+        }
+        LocaleFormat::Json => fs::write(
+            dir.join("index.json"),
+            serde_json::to_string_pretty(&index)?,
+        ),
+    }
+}
+
+/// 语言名中不能用于TS标识符的字符替换为下划线, 例如 zh-CN -> CXTranslationText_zh_CN
+fn locale_module_name(locale: &str) -> String {
+    let locale: String = locale
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("CXTranslationText_{}", locale)
+}
+
+/// 检查每种语言的缺失翻译、与源文本相同的翻译、源文本修改后没有更新的翻译、
+/// 与源文本不一致的占位符以及不匹配的富文本标签, 并更新输出目录下的翻译状态文件
+pub fn check_translations(
+    table: &SheetTable,
+    source_locale: Option<&str>,
+    output_dir: &path::Path,
+    issues: &mut Vec<TranslationIssue>,
+) -> io::Result<()> {
+    let locales: Vec<(usize, &str)> = locale_columns(table).collect();
+    let Some((source_index, _)) = source_column(table, source_locale) else {
+        return Ok(());
+    };
+
+    let state_path = output_dir.join(TRANSLATION_STATE_FILE);
+    let previous: TranslationState = read_json_or_default(&state_path);
+    let mut state = TranslationState::new();
+
+    for row in &table.rows {
+        let key = &row.values[0];
+        let source = &row.values[source_index];
+        let source_hash = text_hash(source);
+        let source_placeholders = placeholders(source);
+        for &(column_index, locale) in &locales {
+            let text = &row.values[column_index];
+            let mut push_issue = |kind, detail| {
+                issues.push(TranslationIssue {
+                    key: key.clone(),
+                    locale: locale.to_string(),
+                    kind,
+                    detail,
+                    source: source.clone(),
+                    text: text.clone(),
+                })
+            };
+
+            // 富文本标签不匹配会导致客户端Label渲染崩溃, 源文本也要检查
+            if let Err(detail) = check_rich_text_tags(text) {
+                push_issue(TranslationIssueKind::UnbalancedTag, detail);
+            }
+            if column_index == source_index {
+                continue;
+            }
+
+            let hash = text_hash(text);
+            let locale_state = match previous.get(key).and_then(|l| l.get(locale)) {
+                Some(previous) if previous.text == hash => previous.clone(),
+                _ => LocaleState {
+                    text: hash,
+                    source: source_hash.clone(),
+                },
+            };
+
+            if text.trim().is_empty() {
+                push_issue(TranslationIssueKind::Missing, String::new());
+            } else {
+                if text == source {
+                    push_issue(TranslationIssueKind::SameAsSource, String::new());
+                } else if locale_state.source != source_hash {
+                    push_issue(TranslationIssueKind::Stale, String::new());
+                }
+                let text_placeholders = placeholders(text);
+                if text_placeholders != source_placeholders {
+                    push_issue(
+                        TranslationIssueKind::PlaceholderMismatch,
+                        format!(
+                            "源文本 [{}], 翻译 [{}]",
+                            source_placeholders.join(" "),
+                            text_placeholders.join(" ")
+                        ),
+                    );
+                }
+            }
+            state
+                .entry(key.clone())
+                .or_default()
+                .insert(locale.to_string(), locale_state);
+        }
+    }
+
+    fs::create_dir_all(output_dir)?;
+    fs::write(state_path, serde_json::to_string_pretty(&state)?)
+}
+
+/// 文本中的格式占位符, 包括 `{0}`、`{name}` 和 printf 风格的 `%d`、`%1$s`、`%.2f`, 排序后返回
+fn placeholders(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '{' => {
+                let end = chars[i + 1..].iter().position(|&c| c == '}');
+                if let Some(len) = end {
+                    let name = &chars[i + 1..i + 1 + len];
+                    if !name.is_empty() && name.iter().all(|c| c.is_alphanumeric() || *c == '_') {
+                        result.push(chars[i..i + len + 2].iter().collect());
+                        i += len + 2;
+                        continue;
+                    }
+                }
+            }
+            '%' if chars.get(i + 1) == Some(&'%') => {
+                i += 2;
+                continue;
+            }
+            '%' => {
+                // %[位置$][标志][宽度][.精度]转换符
+                let mut j = i + 1;
+                while j < chars.len() && (chars[j].is_ascii_digit() || "$-+#.".contains(chars[j])) {
+                    j += 1;
+                }
+                if j < chars.len() && "sdifuxXeEgGc@".contains(chars[j]) {
+                    result.push(chars[i..=j].iter().collect());
+                    i = j + 1;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    result.sort();
+    result
+}
+
+/// Cocos富文本中的一段
+pub enum RichText<'a> {
+    Text(&'a str),
+    /// `<` 和 `>` 之间的标签内容, 例如 `color=#ff0000`、`/b`、`br/`
+    Tag(&'a str),
+    /// 缺少 `>` 的标签, 到文本末尾, 不含开头的 `<`
+    UnclosedTag(&'a str),
+}
+
+/// 把富文本拆分为文本和标签, `<` 后是字母或 `/` 时才是标签, 否则视为普通文本
+pub fn rich_text_segments(text: &str) -> Vec<RichText<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut position = 0;
+    while let Some(offset) = text[position..].find('<') {
+        let start = position + offset;
+        let after = &text[start + 1..];
+        position = start + 1;
+        let is_tag = after
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '/');
+        if !is_tag {
+            continue;
+        }
+        if start > text_start {
+            segments.push(RichText::Text(&text[text_start..start]));
+        }
+        let Some(end) = after.find('>') else {
+            segments.push(RichText::UnclosedTag(after));
+            return segments;
+        };
+        segments.push(RichText::Tag(&after[..end]));
+        position += end + 1;
+        text_start = position;
+    }
+    if text_start < text.len() {
+        segments.push(RichText::Text(&text[text_start..]));
+    }
+    segments
+}
+
+/// Cocos富文本标签匹配检查, 例如 `<color=#ff0000>…</color>`; `<br/>`、`<img .../>` 等自闭合标签不需要闭合
+fn check_rich_text_tags(text: &str) -> Result<(), String> {
+    let mut stack: Vec<String> = Vec::new();
+    for segment in rich_text_segments(text) {
+        let tag = match segment {
+            RichText::Text(_) => continue,
+            RichText::Tag(tag) => tag.trim(),
+            RichText::UnclosedTag(rest) => return Err(format!("标签缺少'>': <{}", rest)),
+        };
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim().to_ascii_lowercase();
+            match stack.pop() {
+                Some(open) if open == name => {}
+                Some(open) => return Err(format!("</{}> 与 <{}> 不匹配", name, open)),
+                None => return Err(format!("多余的结束标签 </{}>", name)),
+            }
+            continue;
+        }
+        if tag.ends_with('/') {
+            continue;
+        }
+        let name = tag
+            .split(|c: char| c == '=' || c.is_whitespace())
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if name != "br" && name != "img" {
+            stack.push(name);
+        }
+    }
+    match stack.pop() {
+        Some(open) => Err(format!("<{}> 没有结束标签", open)),
+        None => Ok(()),
+    }
+}
+
+/// 状态文件只需区分文本是否变化, 取SHA-256的前16位以减小文件体积
+fn text_hash(text: &str) -> String {
+    sha256_hex(text)[..16].to_string()
+}
+
+/// 导出给翻译供应商的CSV, 带BOM以便Excel正确识别UTF-8
+pub fn write_issues_csv(issues: &[TranslationIssue], path: &path::Path) -> io::Result<()> {
+    let quote = |value: &str| format!("\"{}\"", value.replace('"', "\"\""));
+    let mut content = String::from("\u{feff}key,locale,issue,detail,source,translation\n");
+    for issue in issues {
+        content.push_str(
+            &[
+                quote(&issue.key),
+                quote(&issue.locale),
+                quote(issue.kind.label()),
+                quote(&issue.detail),
+                quote(&issue.source),
+                quote(&issue.text),
+            ]
+            .join(","),
+        );
+        content.push('\n');
+    }
+    fs::write(path, content)
+}
+
+/// 翻译检查结果, 点击导出CSV时返回写入结果
+pub fn translation_report_ui(
+    issues: &[TranslationIssue],
+    ui: &mut egui::Ui,
+) -> Option<io::Result<path::PathBuf>> {
+    if issues.is_empty() {
+        return None;
+    }
+    let mut export_result = None;
+    // 按语言汇总, key引用检查的问题不属于任何语言, 按问题类型汇总
+    let mut locales: BTreeMap<&str, usize> = BTreeMap::new();
+    for issue in issues {
+        let group = if issue.locale.is_empty() {
+            issue.kind.label()
+        } else {
+            &issue.locale
+        };
+        *locales.entry(group).or_default() += 1;
+    }
+    let summary = locales
+        .iter()
+        .map(|(locale, count)| format!("{} {}条", locale, count))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    egui::CollapsingHeader::new(format!("翻译检查: {}", summary))
+        .id_salt("translation_report")
+        .show(ui, |ui| {
+            if ui.button("导出CSV").clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .set_file_name("translation_report.csv")
+                    .save_file()
+                {
+                    export_result = Some(write_issues_csv(issues, &path).map(|_| path));
+                }
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("translation_report_scroll")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("translation_report_grid")
+                        .num_columns(6)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("key");
+                            ui.strong("语言");
+                            ui.strong("问题");
+                            ui.strong("说明");
+                            ui.strong("源文本");
+                            ui.strong("翻译");
+                            ui.end_row();
+                            for issue in issues {
+                                ui.label(&issue.key);
+                                ui.label(&issue.locale);
+                                ui.label(issue.kind.label());
+                                ui.label(&issue.detail);
+                                ui.label(&issue.source);
+                                ui.label(&issue.text);
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+    export_result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rich_text_tokenizer() {
+        let segments: Vec<String> = rich_text_segments("a<b>x</b> 1<2 <br/>c")
+            .into_iter()
+            .map(|segment| match segment {
+                RichText::Text(text) => format!("T:{}", text),
+                RichText::Tag(tag) => format!("G:{}", tag),
+                RichText::UnclosedTag(rest) => format!("U:{}", rest),
+            })
+            .collect();
+        assert_eq!(
+            segments,
+            ["T:a", "G:b", "T:x", "G:/b", "T: 1<2 ", "G:br/", "T:c"]
+        );
+        assert!(matches!(
+            rich_text_segments("x<b")[..],
+            [RichText::Text("x"), RichText::UnclosedTag("b")]
+        ));
+    }
+
+    #[test]
+    fn rich_text_tag_balance() {
+        assert!(check_rich_text_tags("<color=#ff0000>红<b>字</b></color><br/>").is_ok());
+        assert!(check_rich_text_tags("1 < 2 <3").is_ok());
+        assert!(check_rich_text_tags("<b>x").is_err());
+        assert!(check_rich_text_tags("<b>x</i>").is_err());
+        assert!(check_rich_text_tags("x</b>").is_err());
+        assert!(check_rich_text_tags("x<b").is_err());
+    }
+}
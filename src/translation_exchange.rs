@@ -0,0 +1,812 @@
+use crate::export_rules::ExportRules;
+use crate::translation::{LANGUAGE_TABLE, locale_columns, source_column};
+use crate::xlsx2csv::{SheetTable, Xlsx2CsvTool};
+use eframe::egui;
+use quick_xml::events::Event;
+use quick_xml::{Reader, escape::escape};
+use std::collections::HashMap;
+use std::io::{self, Error};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fmt::Write as _, fs};
+
+/// 翻译供应商使用的交换格式, 每种目标语言导出一个文件, key 作为 XLIFF 的 trans-unit id 和 PO 的 msgctxt
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ExchangeFormat {
+    Xliff,
+    Po,
+}
+
+impl ExchangeFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExchangeFormat::Xliff => "xlf",
+            ExchangeFormat::Po => "po",
+        }
+    }
+}
+
+/// 交换文件中的一条翻译, source 为导出时的源文本
+struct ExchangeUnit {
+    key: String,
+    source: String,
+    target: String,
+}
+
+struct ExchangeFile {
+    locale: String,
+    units: Vec<ExchangeUnit>,
+}
+
+/// 导入结果, 源文本在导出后被修改的翻译作为冲突, 不会写入合并文件
+#[derive(Default)]
+pub struct ImportReport {
+    pub files: usize,
+    pub updates: Vec<ImportUpdate>,
+    pub unchanged: usize,
+    pub conflicts: Vec<ImportConflict>,
+    /// 文件名和Language表中不存在的key
+    pub unknown_keys: Vec<(String, String)>,
+}
+
+pub struct ImportUpdate {
+    pub key: String,
+    pub locale: String,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+pub struct ImportConflict {
+    pub file: String,
+    pub key: String,
+    pub locale: String,
+    pub exported_source: String,
+    pub current_source: String,
+    pub text: String,
+}
+
+/// 为源语言以外的每种语言导出一个交换文件, 源文本为空的行不导出; 返回写入的文件
+pub fn export_exchange_files(
+    table: &SheetTable,
+    source_locale: Option<&str>,
+    dir: &Path,
+    format: ExchangeFormat,
+) -> io::Result<Vec<PathBuf>> {
+    let Some((source_index, source_name)) = source_column(table, source_locale) else {
+        return Err(Error::new(
+            io::ErrorKind::Other,
+            format!("{}表没有源语言列", LANGUAGE_TABLE),
+        ));
+    };
+    fs::create_dir_all(dir)?;
+
+    let mut written = Vec::new();
+    for (column_index, locale) in locale_columns(table) {
+        if column_index == source_index {
+            continue;
+        }
+        let units: Vec<ExchangeUnit> = table
+            .rows
+            .iter()
+            .filter(|row| !row.values[source_index].is_empty())
+            .map(|row| ExchangeUnit {
+                key: row.values[0].clone(),
+                source: row.values[source_index].clone(),
+                target: row.values[column_index].clone(),
+            })
+            .collect();
+        let content = match format {
+            ExchangeFormat::Xliff => xliff_content(source_name, locale, &units),
+            ExchangeFormat::Po => po_content(source_name, locale, &units),
+        };
+        let path = dir.join(format!(
+            "{}.{}.{}",
+            LANGUAGE_TABLE,
+            locale,
+            format.extension()
+        ));
+        fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+fn xliff_content(source_locale: &str, locale: &str, units: &[ExchangeUnit]) -> String {
+    let mut content = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    content.push_str("<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n");
+    let _ = writeln!(
+        content,
+        "  <file original=\"{}\" source-language=\"{}\" target-language=\"{}\" datatype=\"plaintext\">",
+        LANGUAGE_TABLE,
+        escape(source_locale),
+        escape(locale)
+    );
+    content.push_str("    <body>\n");
+    for unit in units {
+        let state = if unit.target.is_empty() {
+            "needs-translation"
+        } else {
+            "translated"
+        };
+        let _ = writeln!(
+            content,
+            "      <trans-unit id=\"{}\" xml:space=\"preserve\">\n        <source>{}</source>\n        <target state=\"{}\">{}</target>\n      </trans-unit>",
+            escape(&unit.key),
+            escape(&unit.source),
+            state,
+            escape(&unit.target)
+        );
+    }
+    content.push_str("    </body>\n  </file>\n</xliff>\n");
+    content
+}
+
+fn po_content(source_locale: &str, locale: &str, units: &[ExchangeUnit]) -> String {
+    let mut content = String::new();
+    let _ = write!(
+        content,
+        "msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\"Language: {}\\n\"\n\"X-Source-Language: {}\\n\"\n",
+        po_escape(locale),
+        po_escape(source_locale)
+    );
+    for unit in units {
+        let _ = write!(
+            content,
+            "\nmsgctxt \"{}\"\nmsgid \"{}\"\nmsgstr \"{}\"\n",
+            po_escape(&unit.key),
+            po_escape(&unit.source),
+            po_escape(&unit.target)
+        );
+    }
+    content
+}
+
+fn po_escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+fn po_unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// 解析XLIFF 1.2, 目标语言取 file 元素的 target-language; source/target 内的行内标签只保留文本
+fn parse_xliff(content: &str) -> io::Result<ExchangeFile> {
+    let xml_error =
+        |e: quick_xml::Error| Error::new(io::ErrorKind::Other, format!("XLIFF解析失败: {}", e));
+    let mut reader = Reader::from_str(content);
+    let mut file = ExchangeFile {
+        locale: String::new(),
+        units: Vec::new(),
+    };
+    let mut unit: Option<ExchangeUnit> = None;
+    // 当前正在读取的是 source(true) 还是 target(false)
+    let mut reading_source: Option<bool> = None;
+
+    loop {
+        match reader.read_event().map_err(xml_error)? {
+            Event::Start(e) => match e.local_name().as_ref() {
+                b"file" => {
+                    if let Some(attr) = e
+                        .try_get_attribute("target-language")
+                        .map_err(|e| xml_error(e.into()))?
+                    {
+                        file.locale = attr.unescape_value().map_err(xml_error)?.into_owned();
+                    }
+                }
+                b"trans-unit" => {
+                    let key = match e.try_get_attribute("id").map_err(|e| xml_error(e.into()))? {
+                        Some(attr) => attr.unescape_value().map_err(xml_error)?.into_owned(),
+                        None => String::new(),
+                    };
+                    unit = Some(ExchangeUnit {
+                        key,
+                        source: String::new(),
+                        target: String::new(),
+                    });
+                }
+                b"source" => reading_source = Some(true),
+                b"target" => reading_source = Some(false),
+                _ => {}
+            },
+            Event::End(e) => match e.local_name().as_ref() {
+                b"trans-unit" => file.units.extend(unit.take()),
+                b"source" | b"target" => reading_source = None,
+                _ => {}
+            },
+            Event::Text(e) => {
+                if let (Some(unit), Some(is_source)) = (unit.as_mut(), reading_source) {
+                    let text = e.unescape().map_err(xml_error)?;
+                    if is_source {
+                        unit.source.push_str(&text);
+                    } else {
+                        unit.target.push_str(&text);
+                    }
+                }
+            }
+            Event::CData(e) => {
+                if let (Some(unit), Some(is_source)) = (unit.as_mut(), reading_source) {
+                    let text = String::from_utf8_lossy(&e);
+                    if is_source {
+                        unit.source.push_str(&text);
+                    } else {
+                        unit.target.push_str(&text);
+                    }
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(file)
+}
+
+#[derive(Clone, Copy)]
+enum PoField {
+    Context,
+    Id,
+    Text,
+}
+
+#[derive(Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: Option<String>,
+    text: Option<String>,
+    fuzzy: bool,
+}
+
+/// 解析gettext PO, 目标语言取文件头的 Language; key 取 msgctxt, 没有 msgctxt 时取 msgid,
+/// 标记为 fuzzy 的条目视为未翻译
+fn parse_po(content: &str) -> io::Result<ExchangeFile> {
+    let mut entries = Vec::new();
+    let mut entry = PoEntry::default();
+    let mut field: Option<PoField> = None;
+
+    for (line_index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('#') {
+            // 注释属于下一条目
+            if entry.text.is_some() {
+                entries.push(std::mem::take(&mut entry));
+                field = None;
+            }
+            if line
+                .strip_prefix("#,")
+                .is_some_and(|flags| flags.contains("fuzzy"))
+            {
+                entry.fuzzy = true;
+            }
+            continue;
+        }
+
+        let (keyword, value) = if line.starts_with('"') {
+            (None, line)
+        } else {
+            let (keyword, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            (Some(keyword), value.trim())
+        };
+        let Some(value) = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+        else {
+            return Err(Error::new(
+                io::ErrorKind::Other,
+                format!("PO第{}行格式错误: {}", line_index + 1, line),
+            ));
+        };
+        let value = po_unescape(value);
+
+        match keyword {
+            Some("msgctxt" | "msgid") if entry.text.is_some() => {
+                entries.push(std::mem::take(&mut entry));
+            }
+            _ => {}
+        }
+        field = match keyword {
+            Some("msgctxt") => Some(PoField::Context),
+            Some("msgid") => Some(PoField::Id),
+            Some("msgstr") => Some(PoField::Text),
+            // 不处理复数形式
+            Some(_) => None,
+            None => field,
+        };
+        let slot = match field {
+            Some(PoField::Context) => &mut entry.context,
+            Some(PoField::Id) => &mut entry.id,
+            Some(PoField::Text) => &mut entry.text,
+            None => continue,
+        };
+        slot.get_or_insert_with(String::new).push_str(&value);
+    }
+    if entry.text.is_some() {
+        entries.push(entry);
+    }
+
+    let mut file = ExchangeFile {
+        locale: String::new(),
+        units: Vec::new(),
+    };
+    for entry in entries {
+        let id = entry.id.unwrap_or_default();
+        let text = entry.text.unwrap_or_default();
+        if entry.context.is_none() && id.is_empty() {
+            // 文件头
+            for header in text.lines() {
+                if let Some(locale) = header.strip_prefix("Language:") {
+                    file.locale = locale.trim().to_string();
+                }
+            }
+            continue;
+        }
+        file.units.push(ExchangeUnit {
+            key: entry.context.unwrap_or_else(|| id.clone()),
+            source: id,
+            target: if entry.fuzzy { String::new() } else { text },
+        });
+    }
+    Ok(file)
+}
+
+/// 导入翻译文件, 与当前Language表对比得到需要更新的翻译; 未翻译的条目跳过
+pub fn import_exchange_files(
+    table: &SheetTable,
+    source_locale: Option<&str>,
+    files: &[PathBuf],
+) -> io::Result<ImportReport> {
+    let Some((source_index, _)) = source_column(table, source_locale) else {
+        return Err(Error::new(
+            io::ErrorKind::Other,
+            format!("{}表没有源语言列", LANGUAGE_TABLE),
+        ));
+    };
+    let rows: HashMap<&str, usize> = table
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| (row.values[0].as_str(), i))
+        .collect();
+
+    let mut report = ImportReport::default();
+    for path in files {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let content = fs::read_to_string(path)?;
+        let is_po = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("po"));
+        let file = if is_po {
+            parse_po(&content)
+        } else {
+            parse_xliff(&content)
+        }
+        .map_err(|e| Error::new(io::ErrorKind::Other, format!("{}: {}", file_name, e)))?;
+
+        let Some((column_index, locale)) =
+            locale_columns(table).find(|(_, locale)| *locale == file.locale)
+        else {
+            return Err(Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "{}: {}表中没有语言列 \"{}\"",
+                    file_name, LANGUAGE_TABLE, file.locale
+                ),
+            ));
+        };
+        report.files += 1;
+
+        for unit in file.units {
+            if unit.target.is_empty() {
+                continue;
+            }
+            let Some(&row_index) = rows.get(unit.key.as_str()) else {
+                report.unknown_keys.push((file_name.clone(), unit.key));
+                continue;
+            };
+            let row = &table.rows[row_index];
+            let current_source = &row.values[source_index];
+            if &unit.source != current_source {
+                report.conflicts.push(ImportConflict {
+                    file: file_name.clone(),
+                    key: unit.key,
+                    locale: locale.to_string(),
+                    exported_source: unit.source,
+                    current_source: current_source.clone(),
+                    text: unit.target,
+                });
+                continue;
+            }
+            if unit.target == row.values[column_index] {
+                report.unchanged += 1;
+                continue;
+            }
+            report.updates.push(ImportUpdate {
+                key: unit.key,
+                locale: locale.to_string(),
+                old_text: row.values[column_index].clone(),
+                new_text: unit.target,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// 合并CSV: 与Language表相同的列, 只包含有更新的行, 可直接粘贴覆盖工作簿中对应的行
+pub fn write_merge_csv(table: &SheetTable, report: &ImportReport, path: &Path) -> io::Result<()> {
+    let quote = |value: &str| format!("\"{}\"", value.replace('"', "\"\""));
+    let mut updated: Vec<(usize, Vec<String>)> = Vec::new();
+    for update in &report.updates {
+        let Some(row_index) = table.rows.iter().position(|r| r.values[0] == update.key) else {
+            continue;
+        };
+        let Some((column_index, _)) =
+            locale_columns(table).find(|(_, locale)| *locale == update.locale)
+        else {
+            continue;
+        };
+        let values = match updated.iter_mut().find(|(i, _)| *i == row_index) {
+            Some((_, values)) => values,
+            None => {
+                updated.push((row_index, table.rows[row_index].values.clone()));
+                &mut updated.last_mut().unwrap().1
+            }
+        };
+        values[column_index] = update.new_text.clone();
+    }
+    updated.sort_by_key(|(row_index, _)| *row_index);
+
+    let mut content = String::from("\u{feff}");
+    let header: Vec<String> = table.columns.iter().map(|c| quote(&c.name)).collect();
+    content.push_str(&header.join(","));
+    content.push('\n');
+    for (_, values) in &updated {
+        let values: Vec<String> = values.iter().map(|v| quote(v)).collect();
+        content.push_str(&values.join(","));
+        content.push('\n');
+    }
+    fs::write(path, content)
+}
+
+/// 导入结果
+pub fn import_report_ui(report: &ImportReport, ui: &mut egui::Ui) {
+    let summary = format!(
+        "导入{}个文件: 更新{}条, 未变化{}条, 冲突{}条, 未知key {}条",
+        report.files,
+        report.updates.len(),
+        report.unchanged,
+        report.conflicts.len(),
+        report.unknown_keys.len()
+    );
+    egui::CollapsingHeader::new(summary)
+        .id_salt("translation_import_report")
+        .show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("translation_import_scroll")
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    if !report.conflicts.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            "以下翻译导出后源文本已修改, 未写入合并文件:",
+                        );
+                        egui::Grid::new("translation_import_conflicts")
+                            .num_columns(6)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("文件");
+                                ui.strong("key");
+                                ui.strong("语言");
+                                ui.strong("导出时源文本");
+                                ui.strong("当前源文本");
+                                ui.strong("翻译");
+                                ui.end_row();
+                                for conflict in &report.conflicts {
+                                    ui.label(&conflict.file);
+                                    ui.label(&conflict.key);
+                                    ui.label(&conflict.locale);
+                                    ui.label(&conflict.exported_source);
+                                    ui.label(&conflict.current_source);
+                                    ui.label(&conflict.text);
+                                    ui.end_row();
+                                }
+                            });
+                    }
+                    for (file, key) in &report.unknown_keys {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!("{}: 未知的key {}", file, key),
+                        );
+                    }
+                    if !report.updates.is_empty() {
+                        egui::Grid::new("translation_import_updates")
+                            .num_columns(4)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("key");
+                                ui.strong("语言");
+                                ui.strong("原翻译");
+                                ui.strong("新翻译");
+                                ui.end_row();
+                                for update in &report.updates {
+                                    ui.label(&update.key);
+                                    ui.label(&update.locale);
+                                    ui.label(&update.old_text);
+                                    ui.label(&update.new_text);
+                                    ui.end_row();
+                                }
+                            });
+                    }
+                });
+        });
+}
+
+fn load_language_table(excel_dir: &str) -> io::Result<(SheetTable, Option<String>)> {
+    let rules = ExportRules::load(excel_dir)?;
+    let table = Xlsx2CsvTool::new(excel_dir.to_string(), String::new(), Vec::new())
+        .read_table(LANGUAGE_TABLE)?
+        .ok_or_else(|| {
+            Error::new(
+                io::ErrorKind::Other,
+                format!("Excel目录中没有{}表", LANGUAGE_TABLE),
+            )
+        })?;
+    Ok((table, rules.translation.source_locale))
+}
+
+fn export_clicked(app: &mut crate::App, format: ExchangeFormat) {
+    let Some(dir) = rfd::FileDialog::new().pick_folder() else {
+        return;
+    };
+    let result = load_language_table(&app.excel_dir).and_then(|(table, source_locale)| {
+        export_exchange_files(&table, source_locale.as_deref(), &dir, format)
+    });
+    match result {
+        Ok(files) => {
+            app.toasts
+                .success(format!("已导出{}个翻译文件", files.len()))
+                .duration(Duration::from_secs(5).into());
+        }
+        Err(err) => {
+            app.toasts
+                .error(format!("导出翻译文件失败: {}", err))
+                .duration(Duration::from_secs(5).into());
+        }
+    }
+}
+
+fn import_clicked(app: &mut crate::App) {
+    let Some(files) = rfd::FileDialog::new()
+        .add_filter("XLIFF / PO", &["xlf", "xliff", "po"])
+        .pick_files()
+    else {
+        return;
+    };
+    let result = load_language_table(&app.excel_dir).and_then(|(table, source_locale)| {
+        let report = import_exchange_files(&table, source_locale.as_deref(), &files)?;
+        if report.updates.is_empty() {
+            return Ok((report, None));
+        }
+        let Some(path) = rfd::FileDialog::new()
+            .set_file_name(format!("{}_merge.csv", LANGUAGE_TABLE))
+            .save_file()
+        else {
+            return Ok((report, None));
+        };
+        write_merge_csv(&table, &report, &path)?;
+        Ok((report, Some(path)))
+    });
+    match result {
+        Ok((report, merge_path)) => {
+            let message = match merge_path {
+                Some(path) => format!(
+                    "导入完成, {}条更新已写入 {}",
+                    report.updates.len(),
+                    path.display()
+                ),
+                None => format!("导入完成, {}条更新", report.updates.len()),
+            };
+            if report.conflicts.is_empty() {
+                app.toasts
+                    .success(message)
+                    .duration(Duration::from_secs(5).into());
+            } else {
+                app.toasts
+                    .error(format!("{}, {}条冲突", message, report.conflicts.len()))
+                    .duration(Duration::from_secs(5).into());
+            }
+            app.translation_import = Some(report);
+        }
+        Err(err) => {
+            app.toasts
+                .error(format!("导入翻译文件失败: {}", err))
+                .duration(Duration::from_secs(5).into());
+        }
+    }
+}
+
+pub fn translation_exchange_ui(app: &mut crate::App, ui: &mut egui::Ui) {
+    ui.label("翻译交换(XLIFF/PO)");
+    ui.add_space(5.0);
+
+    ui.horizontal(|ui| {
+        let button_size = egui::vec2(150.0, 30.0);
+        if ui
+            .add(egui::Button::new("导出XLIFF").min_size(button_size))
+            .clicked()
+        {
+            export_clicked(app, ExchangeFormat::Xliff);
+        }
+        if ui
+            .add(egui::Button::new("导出PO").min_size(button_size))
+            .clicked()
+        {
+            export_clicked(app, ExchangeFormat::Po);
+        }
+        if ui
+            .add(egui::Button::new("导入翻译文件").min_size(button_size))
+            .clicked()
+        {
+            import_clicked(app);
+        }
+    });
+
+    if let Some(report) = &app.translation_import {
+        import_report_ui(report, ui);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units() -> Vec<ExchangeUnit> {
+        vec![
+            ExchangeUnit {
+                key: "hello".to_string(),
+                source: "你好\n第二行".to_string(),
+                target: "Hello\nsecond \"line\"".to_string(),
+            },
+            ExchangeUnit {
+                key: "tag&<key>".to_string(),
+                source: "<color=#f00>{0}</color> & 'x'".to_string(),
+                target: String::new(),
+            },
+            ExchangeUnit {
+                key: "path".to_string(),
+                source: "a\\b\tc".to_string(),
+                target: "a\\b\tc".to_string(),
+            },
+        ]
+    }
+
+    fn assert_units(file: &ExchangeFile, expected: &[ExchangeUnit]) {
+        assert_eq!(file.units.len(), expected.len());
+        for (unit, expected) in file.units.iter().zip(expected) {
+            assert_eq!(unit.key, expected.key);
+            assert_eq!(unit.source, expected.source);
+            assert_eq!(unit.target, expected.target);
+        }
+    }
+
+    #[test]
+    fn xliff_round_trip() {
+        let file = parse_xliff(&xliff_content("zh", "en-US", &units())).unwrap();
+        assert_eq!(file.locale, "en-US");
+        assert_units(&file, &units());
+    }
+
+    #[test]
+    fn po_round_trip() {
+        let file = parse_po(&po_content("zh", "en-US", &units())).unwrap();
+        assert_eq!(file.locale, "en-US");
+        assert_units(&file, &units());
+    }
+
+    #[test]
+    fn xliff_entities_and_cdata() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2"><file target-language="ja"><body>
+  <trans-unit id="a&amp;b"><source>&lt;b&gt;x&lt;/b&gt; &quot;&#x4E2D;&quot;</source>
+    <target><![CDATA[<b>y</b>]]> &amp; z</target></trans-unit>
+</body></file></xliff>"#;
+        let file = parse_xliff(content).unwrap();
+        assert_eq!(file.locale, "ja");
+        assert_units(
+            &file,
+            &[ExchangeUnit {
+                key: "a&b".to_string(),
+                source: "<b>x</b> \"中\"".to_string(),
+                target: "<b>y</b> & z".to_string(),
+            }],
+        );
+    }
+
+    #[test]
+    fn po_multiline_fuzzy_and_header() {
+        let content = r#"# translator comment
+msgid ""
+msgstr ""
+"Content-Type: text/plain; charset=UTF-8\n"
+"Language: fr\n"
+
+msgctxt "multi"
+msgid ""
+"first line\n"
+"second line"
+msgstr "premiere\n"
+"deuxieme"
+
+#, fuzzy
+msgctxt "fuzzy"
+msgid "old"
+msgstr "ancien"
+
+msgid "no context"
+msgstr "sans contexte"
+"#;
+        let file = parse_po(content).unwrap();
+        assert_eq!(file.locale, "fr");
+        assert_units(
+            &file,
+            &[
+                ExchangeUnit {
+                    key: "multi".to_string(),
+                    source: "first line\nsecond line".to_string(),
+                    target: "premiere\ndeuxieme".to_string(),
+                },
+                ExchangeUnit {
+                    key: "fuzzy".to_string(),
+                    source: "old".to_string(),
+                    target: String::new(),
+                },
+                ExchangeUnit {
+                    key: "no context".to_string(),
+                    source: "no context".to_string(),
+                    target: "sans contexte".to_string(),
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn po_header_only() {
+        let file = parse_po("msgid \"\"\nmsgstr \"Language: de\\n\"\n").unwrap();
+        assert_eq!(file.locale, "de");
+        assert!(file.units.is_empty());
+    }
+
+    #[test]
+    fn po_malformed_line() {
+        assert!(parse_po("msgid \"unterminated\nmsgstr \"\"\n").is_err());
+    }
+}
@@ -0,0 +1,192 @@
+use crate::file_utils::list_files_recursive;
+use crate::translation::{TranslationIssue, TranslationIssueKind};
+use crate::xlsx2csv::SheetTable;
+use std::collections::{BTreeMap, HashSet};
+use std::{fs, io, path};
+
+/// 客户端脚本目录, 相对客户端工程目录
+pub const CLIENT_SCRIPTS_DIR: &str = "assets/scripts";
+/// 翻译key联合类型的文件名
+pub const TRANSLATION_KEYS_TS_FILE: &str = "CXTranslationKey.ts";
+
+/// 导出所有翻译key的字符串字面量联合类型, 客户端引用不存在的key时编译报错
+pub fn write_translation_keys_ts(table: &SheetTable, dir: &path::Path) -> io::Result<()> {
+    let mut keys: Vec<&str> = table
+        .rows
+        .iter()
+        .map(|row| row.values[0].as_str())
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    let union = if keys.is_empty() {
+        " never".to_string()
+    } else {
+        keys.iter()
+            .map(|key| serde_json::to_string(key).map(|key| format!("\n    | {}", key)))
+            .collect::<Result<String, _>>()?
+    };
+    let ts_content = format!(
+        "/** Language表中的所有key */\nexport type CXTranslationKey ={};\n",
+        union
+    );
+    fs::write(dir.join(TRANSLATION_KEYS_TS_FILE), ts_content)
+}
+
+/// 扫描客户端脚本中的字符串字面量: 作为 `key_functions` 中函数第一个参数且不在Language表中的key
+/// 报告为缺少的key, 没有在任何字面量中出现的key报告为未使用; 跳过导表生成的翻译文件
+pub fn check_key_references(
+    table: &SheetTable,
+    scripts_dir: &path::Path,
+    key_functions: &[String],
+    issues: &mut Vec<TranslationIssue>,
+) -> io::Result<()> {
+    let scripts = list_files_recursive(scripts_dir, |path| {
+        path.extension().is_some_and(|ext| ext == "ts")
+            && !path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("CXTranslation"))
+    })?;
+
+    let keys: HashSet<&str> = table
+        .rows
+        .iter()
+        .map(|row| row.values[0].as_str())
+        .collect();
+    let mut literals: HashSet<String> = HashSet::new();
+    // key -> 首次引用的位置
+    let mut missing: BTreeMap<String, String> = BTreeMap::new();
+    for relative_path in scripts {
+        if relative_path.contains("/locales/") {
+            continue;
+        }
+        let content = fs::read_to_string(scripts_dir.join(&relative_path))?;
+        for literal in string_literals(&content) {
+            let is_key_call = key_functions
+                .iter()
+                .any(|function| is_call_argument(&content[..literal.start], function));
+            if is_key_call && !keys.contains(literal.text.as_str()) {
+                missing
+                    .entry(literal.text.clone())
+                    .or_insert_with(|| format!("{}:{}", relative_path, literal.line));
+            }
+            literals.insert(literal.text);
+        }
+    }
+
+    for (key, location) in missing {
+        issues.push(TranslationIssue {
+            key,
+            locale: String::new(),
+            kind: TranslationIssueKind::UnknownKey,
+            detail: location,
+            source: String::new(),
+            text: String::new(),
+        });
+    }
+    for row in &table.rows {
+        let key = &row.values[0];
+        if !literals.contains(key) {
+            issues.push(TranslationIssue {
+                key: key.clone(),
+                locale: String::new(),
+                kind: TranslationIssueKind::UnusedKey,
+                detail: String::new(),
+                source: String::new(),
+                text: String::new(),
+            });
+        }
+    }
+    Ok(())
+}
+
+struct StringLiteral {
+    text: String,
+    /// 开始引号的字节位置
+    start: usize,
+    line: usize,
+}
+
+/// 字面量之前的代码是否以 `function(` 结尾, 允许 `obj.function(` 这样的方法调用
+fn is_call_argument(before: &str, function: &str) -> bool {
+    let Some(before) = before.trim_end().strip_suffix('(') else {
+        return false;
+    };
+    let Some(before) = before.trim_end().strip_suffix(function) else {
+        return false;
+    };
+    !before
+        .chars()
+        .next_back()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// 提取TS源码中的字符串字面量, 跳过注释和带 `${}` 插值的模板字符串
+fn string_literals(content: &str) -> Vec<StringLiteral> {
+    let mut literals = Vec::new();
+    let mut chars = content.char_indices().peekable();
+    let mut line = 1;
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '/') => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek().is_some_and(|&(_, next)| next == '*') => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' | '\'' | '`' => {
+                let quote = c;
+                let start_line = line;
+                let mut text = String::new();
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        c if c == quote => break,
+                        '\n' if quote != '`' => {
+                            line += 1;
+                            break;
+                        }
+                        '\\' => match chars.next() {
+                            Some((_, 'n')) => text.push('\n'),
+                            Some((_, 't')) => text.push('\t'),
+                            Some((_, '\n')) => line += 1,
+                            Some((_, escaped)) => text.push(escaped),
+                            None => {}
+                        },
+                        c => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            text.push(c);
+                        }
+                    }
+                }
+                if quote != '`' || !text.contains("${") {
+                    literals.push(StringLiteral {
+                        text,
+                        start,
+                        line: start_line,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    literals
+}
@@ -0,0 +1,87 @@
+use crate::column_type::ValueKind;
+use crate::xlsx2csv::{SheetTable, cell_name};
+use unicode_normalization::{UnicodeNormalization, is_nfc};
+
+/// 零宽字符: 零宽空格、零宽不连字、单词连接符; 零宽连字(U+200D)用于组合emoji, 不在此列
+const ZERO_WIDTH_CHARS: &[char] = &['\u{200b}', '\u{200c}', '\u{2060}'];
+const BOM: char = '\u{feff}';
+/// 不间断空格和窄不间断空格
+const NO_BREAK_SPACES: &[char] = &['\u{a0}', '\u{202f}'];
+
+/// 检查一个数据行中的不可见字符和有问题的字符, fix 为 true 时直接修复行中的值:
+/// 删除零宽字符和BOM, 不间断空格替换为普通空格, 数值列的全角数字转半角, 去掉主键首尾空白,
+/// 文本转换为NFC; 每个有问题的单元格报告一条警告. 在必填检查和填充默认值之前调用,
+/// 只含不可见字符的单元格修复后按空单元格处理
+pub fn check_row(
+    table: &SheetTable,
+    values: &mut [String],
+    line: usize,
+    fix: bool,
+    warnings: &mut Vec<String>,
+) {
+    let numeric: Vec<bool> = table
+        .columns
+        .iter()
+        .map(|c| matches!(c.column_type.kind(), ValueKind::Int | ValueKind::Float))
+        .collect();
+
+    for (i, value) in values.iter_mut().enumerate() {
+        let mut problems = Vec::new();
+        if value.contains(ZERO_WIDTH_CHARS) {
+            problems.push("零宽字符");
+        }
+        if value.contains(BOM) {
+            problems.push("BOM");
+        }
+        if value.contains(NO_BREAK_SPACES) {
+            problems.push("不间断空格");
+        }
+        if numeric[i] && value.contains(is_full_width_digit) {
+            problems.push("全角数字");
+        }
+        if i == 0 && value.trim() != value {
+            problems.push("主键首尾有空白");
+        }
+        if !is_nfc(value) {
+            problems.push("非NFC文本");
+        }
+        if problems.is_empty() {
+            continue;
+        }
+
+        let column = &table.columns[i];
+        warnings.push(format!(
+            "<<{}>> {} {}: {}{} ({:?})",
+            table.source,
+            cell_name(column.cell_index, line),
+            column.name,
+            problems.join(", "),
+            if fix { ", 已修复" } else { "" },
+            value
+        ));
+        if fix {
+            *value = fixed_value(value, numeric[i], i == 0);
+        }
+    }
+}
+
+fn is_full_width_digit(c: char) -> bool {
+    ('\u{ff10}'..='\u{ff19}').contains(&c)
+}
+
+fn fixed_value(value: &str, numeric: bool, key: bool) -> String {
+    let value: String = value
+        .chars()
+        .filter(|c| !ZERO_WIDTH_CHARS.contains(c) && *c != BOM)
+        .map(|c| match c {
+            c if NO_BREAK_SPACES.contains(&c) => ' ',
+            // 数值列中的全角数字、小数点和负号转半角
+            '\u{ff10}'..='\u{ff19}' | '\u{ff0e}' | '\u{ff0d}' if numeric => {
+                char::from_u32(c as u32 - 0xfee0).unwrap_or(c)
+            }
+            c => c,
+        })
+        .nfc()
+        .collect();
+    if key { value.trim().to_string() } else { value }
+}
@@ -1,16 +1,53 @@
+use crate::bad_words::{self, BAD_WORDS_TABLE};
+use crate::binary_table;
+use crate::client_assets;
+use crate::column_type::ColumnType;
+use crate::export_diff::{self, EXPORT_DIFF_FILE};
+use crate::export_manifest;
+use crate::export_report::{self, ExportReport, TableReport};
+use crate::export_rules::ExportRules;
+use crate::export_schema;
+use crate::export_target::{
+    ExportTarget, GENERATOR_BAD_WORDS_AUTOMATON, GENERATOR_BINARY_TABLES, GENERATOR_PROTOBUF,
+    GENERATOR_SQLITE, GENERATOR_TRANSLATION_KEYS_TS, GENERATOR_TRANSLATION_LOCALES_JSON,
+    GENERATOR_TRANSLATION_LOCALES_TS, GENERATOR_TRANSLATION_TS, GENERATORS, HeaderStyle,
+};
+use crate::file_utils::list_files_recursive;
+use crate::lua_table;
+use crate::protobuf;
+use crate::sqlite_export;
+use crate::table_chunks;
+use crate::table_meta::{TableFormat, TableMeta};
+use crate::text_width;
+use crate::translation::{self, LANGUAGE_TABLE, LocaleFormat};
+use crate::translation_keys::{self, CLIENT_SCRIPTS_DIR};
+use crate::unicode_hygiene;
 use calamine::{Reader, Xlsx, open_workbook};
 use serde_json;
 use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     fs,
     io::{self, Error},
     path::{self, PathBuf},
 };
 
+/// 字段名行(第2行)中的版本列, 这两列不会导出, 只用于判断该行在目标版本中是否生效:
+/// SINCE_VERSION <= 目标版本 <= UNTIL_VERSION, 空单元格表示不限制
+const SINCE_VERSION_COLUMN: &str = "SINCE_VERSION";
+const UNTIL_VERSION_COLUMN: &str = "UNTIL_VERSION";
+
 pub struct Xlsx2CsvTool {
     pub input_dir: String,
-    pub output_server_dir: PathBuf,
-    pub output_client_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub targets: Vec<ExportTarget>,
     pub files: Vec<String>,
+    pub target_version: String,
+    pub mirror_folders: bool,
+    pub client_dir: String,
+    pub fix_unicode: bool,
+    pub rules: ExportRules,
     pub progress_callback: Option<Box<dyn Fn(i32, i32, String) + Send>>,
 }
 
@@ -18,13 +55,38 @@ impl Xlsx2CsvTool {
     pub fn new(input_dir: String, output_dir: String, files: Vec<String>) -> Self {
         Self {
             input_dir,
-            output_server_dir: PathBuf::from(&output_dir).join("server"),
-            output_client_dir: PathBuf::from(&output_dir).join("client"),
+            output_dir: PathBuf::from(&output_dir),
+            targets: Vec::new(),
             files,
+            target_version: String::new(),
+            mirror_folders: false,
+            client_dir: String::new(),
+            fix_unicode: false,
+            rules: ExportRules::default(),
             progress_callback: None,
         }
     }
 
+    /// 设置导出的目标版本, 为空时不按版本过滤
+    pub fn set_target_version(&mut self, version: impl Into<String>) {
+        self.target_version = version.into().trim().to_string();
+    }
+
+    /// 设置输出是否保持Excel目录的子目录结构, 否则所有表输出到同一目录
+    pub fn set_mirror_folders(&mut self, mirror_folders: bool) {
+        self.mirror_folders = mirror_folders;
+    }
+
+    /// 设置客户端工程目录, 用于检查客户端脚本引用的翻译key和表中引用的资源, 为空时不检查
+    pub fn set_client_dir(&mut self, client_dir: impl Into<String>) {
+        self.client_dir = client_dir.into();
+    }
+
+    /// 设置是否修复单元格中的不可见字符和非NFC文本, 否则只报告警告
+    pub fn set_fix_unicode(&mut self, fix_unicode: bool) {
+        self.fix_unicode = fix_unicode;
+    }
+
     pub fn set_progress_callback<F>(&mut self, callback: F)
     where
         F: Fn(i32, i32, String) + Send + 'static,
@@ -32,21 +94,52 @@ impl Xlsx2CsvTool {
         self.progress_callback = Some(Box::new(callback));
     }
 
-    pub fn exec(self) -> io::Result<()> {
-        // 清理输出目录
-        if self.output_server_dir.exists() {
-            fs::remove_dir_all(&self.output_server_dir)?;
-        }
-        if self.output_client_dir.exists() {
-            fs::remove_dir_all(&self.output_client_dir)?;
+    pub fn exec(mut self) -> io::Result<ExportReport> {
+        self.rules = ExportRules::load(&self.input_dir)?;
+        self.targets = self.rules.export_targets();
+        for version in std::iter::once(&self.target_version)
+            .chain(self.targets.iter().map(|t| &t.target_version))
+        {
+            if !version.trim().is_empty() && parse_version(version).is_none() {
+                return Err(Error::new(
+                    io::ErrorKind::Other,
+                    format!("目标版本格式错误: {}, 应为点分数字如1.2.0", version),
+                ));
+            }
         }
 
-        // 创建输出目录
-        fs::create_dir_all(&self.output_server_dir)?;
-        fs::create_dir_all(&self.output_client_dir)?;
+        // 导出完成前输出目录不完整, 先记录错误, 导出完成后替换为本次的校验错误
+        fs::create_dir_all(&self.output_dir)?;
+        export_report::save_export_errors(&self.output_dir, &["上次导出未完成".to_string()])?;
+
+        let mut report = ExportReport::default();
+        for target in &self.targets {
+            let target_dir = self.output_dir.join(target.dir());
+
+            // 清理输出目录
+            if target_dir.exists() {
+                fs::remove_dir_all(&target_dir)?;
+            }
+
+            // 创建输出目录
+            fs::create_dir_all(&target_dir)?;
 
-        // 读取输入目录
-        let entries = fs::read_dir(&self.input_dir)?;
+            for generator in &target.generators {
+                if !GENERATORS.contains(&generator.as_str()) {
+                    report.warnings.push(format!(
+                        "导出目标 {} 配置了未知的生成器 {}",
+                        target.name, generator
+                    ));
+                }
+            }
+        }
+
+        // 递归查找输入目录下的xlsx文件, 跳过隐藏文件和临时文件
+        let xlsx_files = list_files_recursive(&self.input_dir, |path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"))
+        })?;
 
         // 处理每个xlsx文件
         let total_files = self.files.len() as i32;
@@ -57,248 +150,898 @@ impl Xlsx2CsvTool {
             callback(processed_files, total_files, String::from(""));
         }
 
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
+        let mut tables = Vec::new();
+        for relative_path in xlsx_files {
+            if !self.files.is_empty() && !self.files.contains(&relative_path) {
+                continue;
+            }
 
-            // 跳过隐藏文件和非xlsx文件
-            if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                if file_name.starts_with(".") || file_name.starts_with("~") {
-                    continue;
-                }
+            processed_files += 1;
+            if let Some(ref callback) = self.progress_callback {
+                callback(processed_files, total_files, relative_path.clone());
+            }
 
-                if !self.files.is_empty() && !self.files.contains(&file_name.to_string()) {
-                    continue;
-                }
+            let sheets = self
+                .read_xlsx(&relative_path, &mut report)
+                .map_err(|e| Error::new(io::ErrorKind::Other, e.to_string()))?;
+            tables.extend(sheets);
+        }
 
-                if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
-                    if extension.eq_ignore_ascii_case("xlsx") {
-                        processed_files += 1;
-                        if let Some(ref callback) = self.progress_callback {
-                            callback(
-                                processed_files,
-                                total_files,
-                                path.to_owned().to_string_lossy().to_string(),
-                            );
-                        }
+        if let Some(language) = tables.iter().find(|t| t.meta.name == LANGUAGE_TABLE) {
+            let source_locale = self.rules.translation.source_locale.as_deref();
+            if let Some(locale) = source_locale
+                .filter(|_| translation::source_column(language, source_locale).is_none())
+            {
+                report.errors.push(format!(
+                    "<<{}>> translation.source_locale 配置的源语言 {} 不是语言列, 跳过翻译检查",
+                    language.source, locale
+                ));
+            }
+            translation::check_translations(
+                language,
+                source_locale,
+                &self.output_dir,
+                &mut report.translation_issues,
+            )?;
 
-                        self.process_xlsx(&path)
-                            .map_err(|e| Error::new(io::ErrorKind::Other, e.to_string()))?;
+            let generates_keys = self.targets.iter().any(|t| {
+                t.includes_table(&language.meta) && t.has_generator(GENERATOR_TRANSLATION_KEYS_TS)
+            });
+            if generates_keys && !self.client_dir.is_empty() {
+                let scripts_dir = path::Path::new(&self.client_dir).join(CLIENT_SCRIPTS_DIR);
+                if scripts_dir.is_dir() {
+                    if self.rules.translation.key_functions.is_empty() {
+                        report.warnings.push(
+                            "translation.key_functions 为空, 不检查客户端脚本中未知的翻译key"
+                                .to_string(),
+                        );
                     }
+                    translation_keys::check_key_references(
+                        language,
+                        &scripts_dir,
+                        &self.rules.translation.key_functions,
+                        &mut report.translation_issues,
+                    )?;
+                } else {
+                    report.warnings.push(format!(
+                        "客户端脚本目录 {} 不存在, 跳过翻译key引用检查",
+                        scripts_dir.display()
+                    ));
                 }
             }
         }
-        Ok(())
+
+        if let Some(bad_words) = tables.iter().find(|t| t.meta.name == BAD_WORDS_TABLE) {
+            let (words, duplicates) = bad_words::word_list(bad_words);
+            if !duplicates.is_empty() {
+                report.warnings.push(format!(
+                    "<<{}>> 规范化后有{}个重复的敏感词: {}",
+                    bad_words.source,
+                    duplicates.len(),
+                    duplicates.join(", ")
+                ));
+            }
+
+            let automaton = bad_words::Automaton::build(&words, duplicates.len());
+            for table in tables.iter().filter(|t| t.meta.name != BAD_WORDS_TABLE) {
+                bad_words::check_table(&automaton, table, &mut report.warnings);
+            }
+        }
+
+        for table in &tables {
+            text_width::check_table(table, &self.rules, &mut report.warnings);
+        }
+
+        if !self.client_dir.is_empty() {
+            client_assets::check_asset_references(
+                &tables,
+                path::Path::new(&self.client_dir),
+                &mut report.errors,
+                &mut report.warnings,
+            )?;
+        }
+
+        self.write_tables(&tables, &mut report)?;
+        self.write_manifests(&tables, &mut report)?;
+        self.diff_with_previous(&tables, &mut report)?;
+        self.check_schema(&tables, &mut report)?;
+        export_report::save_export_errors(&self.output_dir, &report.errors)?;
+        Ok(report)
+    }
+
+    /// 只读取指定名称的表, 不写出文件; 先查找同名工作簿, 找不到时再查找其他工作簿
+    pub fn read_table(mut self, name: &str) -> io::Result<Option<SheetTable>> {
+        self.rules = ExportRules::load(&self.input_dir)?;
+        self.targets = self.rules.export_targets();
+
+        let mut xlsx_files = list_files_recursive(&self.input_dir, |path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xlsx"))
+        })?;
+        xlsx_files.sort_by_key(|relative_path| {
+            path::Path::new(relative_path)
+                .file_stem()
+                .is_none_or(|stem| stem != name)
+        });
+
+        let mut report = ExportReport::default();
+        for relative_path in xlsx_files {
+            let tables = self.read_xlsx(&relative_path, &mut report)?;
+            if let Some(table) = tables.into_iter().find(|t| t.meta.name == name) {
+                return Ok(Some(table));
+            }
+        }
+        Ok(None)
     }
 
-    fn process_xlsx(&self, xlsx_path: &path::Path) -> io::Result<()> {
-        let mut workbook: Xlsx<_> = open_workbook(xlsx_path).map_err(|e| {
+    /// 读取一个工作簿的所有工作表, relative_path 为相对Excel目录的路径
+    fn read_xlsx(
+        &self,
+        relative_path: &str,
+        report: &mut ExportReport,
+    ) -> io::Result<Vec<SheetTable>> {
+        let xlsx_path = PathBuf::from(&self.input_dir).join(relative_path);
+        let mut workbook: Xlsx<_> = open_workbook(&xlsx_path).map_err(|e| {
             Error::new(
                 io::ErrorKind::Other,
                 format!("Failed to open workbook: {}", e),
             )
         })?;
 
-        let base_name = xlsx_path
-            .file_stem()
-            .and_then(|n| n.to_str())
-            .ok_or_else(|| Error::new(io::ErrorKind::Other, "Invalid file name"))?;
+        // 报告和错误信息中使用不带扩展名的相对路径, 以区分不同子目录下的同名文件
+        let base_name = relative_path
+            .strip_suffix(".xlsx")
+            .or_else(|| relative_path.strip_suffix(".XLSX"))
+            .unwrap_or(relative_path);
+        // 保持目录结构时, 表文件输出到工作簿所在的子目录
+        let output_dir = match path::Path::new(relative_path).parent() {
+            Some(parent) if self.mirror_folders => parent.to_path_buf(),
+            _ => PathBuf::new(),
+        };
 
         // 处理每个工作表
+        let mut tables = Vec::new();
         for sheet_name in workbook.sheet_names().to_owned() {
             if let Some(Ok(range)) = workbook.worksheet_range(&sheet_name) {
-                self.process_sheet(base_name, &sheet_name, range)?;
+                if let Some(mut table) = self.read_sheet(base_name, &sheet_name, range, report)? {
+                    table.workbook = relative_path.to_string();
+                    table.output_dir = output_dir.clone();
+                    tables.push(table);
+                }
+            }
+        }
+
+        Ok(tables)
+    }
+
+    /// 写出所有表, 多个工作表导出到同一个文件时只保留第一个并报错
+    fn write_tables(&self, tables: &[SheetTable], report: &mut ExportReport) -> io::Result<()> {
+        let mut proto_fields = protobuf::load_fields(&self.output_dir);
+        let mut outputs: HashMap<PathBuf, &str> = HashMap::new();
+        let mut written = Vec::new();
+        for table in tables {
+            let output = table.output_dir.join(&table.meta.name);
+            if let Some(previous) = outputs.get(&output) {
+                report.errors.push(format!(
+                    "<<{}>> 与 <<{}>> 导出到同一个文件 {}, 已跳过",
+                    table.source,
+                    previous,
+                    output.display()
+                ));
+                continue;
+            }
+            outputs.insert(output, &table.source);
+            written.push(table);
+
+            let mut targets = Vec::new();
+            for target in &self.targets {
+                if !target.includes_table(&table.meta) {
+                    continue;
+                }
+                let target_dir = self.output_dir.join(target.dir());
+                let table = table.released(self.version_for(target));
+                let table = table.as_ref();
+                write_table(table, target, &target_dir)?;
+                if table.meta.name == LANGUAGE_TABLE {
+                    if target.has_generator(GENERATOR_TRANSLATION_TS) {
+                        translation::write_translation_ts(table, &target_dir)?;
+                    }
+                    if target.has_generator(GENERATOR_TRANSLATION_LOCALES_TS) {
+                        translation::write_locale_files(table, &target_dir, LocaleFormat::Ts)?;
+                    }
+                    if target.has_generator(GENERATOR_TRANSLATION_LOCALES_JSON) {
+                        translation::write_locale_files(table, &target_dir, LocaleFormat::Json)?;
+                    }
+                    if target.has_generator(GENERATOR_TRANSLATION_KEYS_TS) {
+                        translation_keys::write_translation_keys_ts(table, &target_dir)?;
+                    }
+                }
+                if table.meta.name == BAD_WORDS_TABLE
+                    && target.has_generator(GENERATOR_BAD_WORDS_AUTOMATON)
+                {
+                    bad_words::write_automaton(table, &target_dir)?;
+                }
+                // 拆分的表按分块按需加载, 不再额外导出整张表的二进制文件
+                if target.has_generator(GENERATOR_BINARY_TABLES) {
+                    if is_chunked(table, target) {
+                        report.warnings.push(format!(
+                            "<<{}>> 拆分的表不导出二进制表 (导出目标 {})",
+                            table.source, target.name
+                        ));
+                    } else {
+                        binary_table::write_binary_table(
+                            table,
+                            target,
+                            &target_dir,
+                            &mut report.warnings,
+                        )?;
+                    }
+                }
+                if target.has_generator(GENERATOR_PROTOBUF) {
+                    protobuf::write_protobuf_table(
+                        table,
+                        target,
+                        &target_dir,
+                        &mut proto_fields,
+                        &mut report.errors,
+                    )?;
+                }
+                targets.push(target.name.clone());
             }
+
+            report.tables.push(TableReport {
+                name: table.meta.name.clone(),
+                source: table.source.clone(),
+                group: table.meta.group.clone().unwrap_or_default(),
+                targets,
+                rows: table.rows.len(),
+                filtered_rows: table.filtered_rows,
+            });
         }
 
+        for target in &self.targets {
+            let target_dir = self.output_dir.join(target.dir());
+            if target.has_generator(GENERATOR_BINARY_TABLES) {
+                binary_table::write_table_reader_ts(&target_dir)?;
+            }
+            if target.has_generator(GENERATOR_SQLITE) {
+                let version = self.version_for(target);
+                let target_tables: Vec<Cow<SheetTable>> = written
+                    .iter()
+                    .filter(|t| target.includes_table(&t.meta))
+                    .map(|t| t.released(version))
+                    .collect();
+                let target_tables: Vec<&SheetTable> =
+                    target_tables.iter().map(|t| t.as_ref()).collect();
+                sqlite_export::write_database(
+                    &target_tables,
+                    target,
+                    &target_dir,
+                    &mut report.errors,
+                )?;
+            }
+        }
+        if self
+            .targets
+            .iter()
+            .any(|t| t.has_generator(GENERATOR_PROTOBUF))
+        {
+            protobuf::save_fields(&self.output_dir, &proto_fields)?;
+        }
         Ok(())
     }
 
-    fn process_sheet(
+    /// 每个导出目标写出带校验和的文件清单, 并递增数据版本
+    fn write_manifests(&self, tables: &[SheetTable], report: &mut ExportReport) -> io::Result<()> {
+        report.data_version = export_manifest::next_data_version(&self.output_dir)?;
+        let export_time = export_manifest::unix_time();
+        let excel_revision = export_manifest::excel_revision(&self.input_dir);
+        for target in &self.targets {
+            let version = self.version_for(target);
+            let target_tables: Vec<Cow<SheetTable>> = tables
+                .iter()
+                .filter(|t| target.includes_table(&t.meta))
+                .map(|t| t.released(version))
+                .collect();
+            let target_tables: Vec<&SheetTable> =
+                target_tables.iter().map(|t| t.as_ref()).collect();
+            export_manifest::write_manifest(
+                target,
+                &self.output_dir.join(target.dir()),
+                &target_tables,
+                report.data_version,
+                export_time,
+                &excel_revision,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 与上次导出的快照按主键对比, 写出差异文件并更新快照; 只导出部分文件时保留其他表的快照
+    fn diff_with_previous(
+        &self,
+        tables: &[SheetTable],
+        report: &mut ExportReport,
+    ) -> io::Result<()> {
+        let previous = export_diff::load_snapshot(&self.output_dir);
+        let mut snapshot = if self.files.is_empty() {
+            export_diff::ExportSnapshot::new()
+        } else {
+            previous.clone()
+        };
+        // 导出到同一个文件的表只有第一个被写出
+        let mut written = HashSet::new();
+        for table in tables {
+            let id = export_diff::table_id(table);
+            if written.insert(id.clone()) {
+                snapshot.insert(id, export_diff::TableSnapshot::from_table(table));
+            }
+        }
+
+        report.diff = export_diff::diff_snapshots(&previous, &snapshot);
+        export_diff::write_diff_text(&report.diff, &self.output_dir.join(EXPORT_DIFF_FILE))?;
+        export_diff::save_snapshot(&self.output_dir, &snapshot)
+    }
+
+    /// 与上次导出的各目标表结构对比, 变更作为警告报告并记录为待确认; 只导出部分文件时保留其他表的结构
+    fn check_schema(&self, tables: &[SheetTable], report: &mut ExportReport) -> io::Result<()> {
+        let previous = export_schema::load_schema(&self.output_dir);
+        let mut schema = if self.files.is_empty() {
+            export_schema::ExportSchema::new()
+        } else {
+            previous.clone()
+        };
+        for target in &self.targets {
+            let target_schema = schema.entry(target.name.clone()).or_default();
+            let mut written = HashSet::new();
+            for table in tables.iter().filter(|t| target.includes_table(&t.meta)) {
+                let id = export_diff::table_id(table);
+                if written.insert(id.clone()) {
+                    target_schema.insert(id, export_schema::table_schema(table, target));
+                }
+            }
+        }
+
+        let changes = export_schema::diff_schema(&previous, &schema);
+        for change in &changes {
+            report
+                .warnings
+                .push(format!("表结构变更 {}", change.message()));
+        }
+        export_schema::add_pending_changes(&self.output_dir, &changes)?;
+        export_schema::save_schema(&self.output_dir, &schema)
+    }
+
+    /// 解析工作表: 前3行依次为注释(A1为表选项)、字段名、类型, 之后为数据行
+    fn read_sheet(
         &self,
         base_name: &str,
         sheet_name: &str,
         range: calamine::Range<calamine::DataType>,
-    ) -> io::Result<()> {
+        report: &mut ExportReport,
+    ) -> io::Result<Option<SheetTable>> {
         let rows: Vec<_> = range.rows().collect();
-        if rows.is_empty() {
-            return Ok(());
-        }
-
-        // 解析标题头和输出类型
-        let mut for_server = false;
-        let mut for_client = false;
-        let mut line_limit = 0;
-
-        if let Some(first_cell) = rows[0].first() {
-            let meta = first_cell.to_string();
-            let metas: Vec<&str> = meta.split('#').collect();
-            let csv_name = metas[0].to_string();
-
-            if metas.len() == 1 {
-                for_server = true;
-                for_client = true;
-            } else {
-                if metas.len() > 1 && metas[1] == "1" {
-                    for_server = true;
+        let Some(first_cell) = rows.first().and_then(|row| row.first()) else {
+            return Ok(None);
+        };
+
+        let (meta, mut warnings) = TableMeta::parse(&first_cell.to_string());
+        for target in meta.targets.iter().flatten() {
+            if !self.targets.iter().any(|t| &t.name == target) {
+                warnings.push(format!("未配置的导出目标: {}", target));
+            }
+        }
+        for warning in warnings {
+            report.warnings.push(format!(
+                "<<{}>> - {} A1: {}",
+                base_name, sheet_name, warning
+            ));
+        }
+
+        if meta.line_limit > 0 && meta.line_limit < 3 {
+            return Err(Error::new(
+                io::ErrorKind::Other,
+                format!(
+                    "CHECK SERVER CSV FILE: <<{}>> - {} ERROR: 行数限制不能小于3, 前3行是必须的头; 不限制请设置为0",
+                    base_name, sheet_name
+                ),
+            ));
+        }
+        let rows = if meta.line_limit > 0 {
+            &rows[..rows.len().min(meta.line_limit)]
+        } else {
+            &rows[..]
+        };
+        let cell_at = |row: usize, cell_index: usize| {
+            rows.get(row)
+                .and_then(|r| r.get(cell_index))
+                .map(|cell| cell.to_string())
+                .unwrap_or_default()
+        };
+
+        // 注释以UNEXPORT_开头、注释或字段名为空的列以及版本列、宽度列不导出
+        let mut cell_indexes = Vec::new();
+        let mut columns = Vec::new();
+        let mut since_index = None;
+        let mut until_index = None;
+        let mut max_width_index = None;
+        for cell_index in 0..rows[0].len() {
+            let comment = cell_at(0, cell_index);
+            let name = cell_at(1, cell_index);
+            match name.trim() {
+                SINCE_VERSION_COLUMN => since_index = Some(cell_index),
+                UNTIL_VERSION_COLUMN => until_index = Some(cell_index),
+                // A1 中 width_column= 指定的行显示宽度列, 空单元格表示不限制;
+                // 用于Language表按key限制每种语言的文本宽度
+                field if meta.width_column.as_deref() == Some(field) => {
+                    max_width_index = Some(cell_index)
                 }
-                if metas.len() > 2 && metas[2] == "1" {
-                    for_client = true;
+                _ if comment.starts_with("UNEXPORT_")
+                    || comment.trim().is_empty()
+                    || name.trim().is_empty() => {}
+                _ => {
+                    let column_type = ColumnType::parse(&cell_at(2, cell_index));
+                    for target in column_type.targets.iter().flatten() {
+                        if !self.targets.iter().any(|t| &t.name == target) {
+                            report.warnings.push(format!(
+                                "<<{}>> - {} {}: 未配置的导出目标: {}",
+                                base_name, sheet_name, name, target
+                            ));
+                        }
+                    }
+                    cell_indexes.push(cell_index);
+                    columns.push(SheetColumn {
+                        comment,
+                        name,
+                        column_type,
+                        cell_index,
+                    });
                 }
-                if metas.len() > 3 {
-                    line_limit = metas[3].parse().unwrap_or(0);
+            }
+        }
+
+        if let Some(width_column) = meta
+            .width_column
+            .as_ref()
+            .filter(|_| max_width_index.is_none())
+        {
+            report.warnings.push(format!(
+                "<<{}>> - {} A1: 找不到宽度列 {}",
+                base_name, sheet_name, width_column
+            ));
+        }
+
+        let mut table = SheetTable {
+            meta,
+            source: format!("{}/{}", base_name, sheet_name),
+            workbook: String::new(),
+            sheet_name: sheet_name.to_string(),
+            output_dir: PathBuf::new(),
+            columns,
+            rows: Vec::new(),
+            filtered_rows: 0,
+        };
+
+        // 导出到本表的各目标的目标版本, 只保留至少在一个目标中生效的行
+        let mut versions: Vec<&str> = self
+            .targets
+            .iter()
+            .filter(|t| t.includes_table(&table.meta))
+            .map(|t| self.version_for(t))
+            .collect();
+        if versions.is_empty() {
+            versions.push(&self.target_version);
+        }
+        for (index, row) in rows.iter().enumerate().skip(3) {
+            let version_at = |cell_index: Option<usize>| {
+                cell_index
+                    .and_then(|i| row.get(i))
+                    .map(|cell| cell.to_string().trim().to_string())
+                    .filter(|v| !v.is_empty())
+            };
+            let since_version = version_at(since_index);
+            let until_version = version_at(until_index);
+            // 版本号无法解析时不能判断是否发布, 报错且不导出该行
+            let mut invalid_version = false;
+            for (cell_index, version) in
+                [(since_index, &since_version), (until_index, &until_version)]
+            {
+                if let (Some(cell_index), Some(version)) = (cell_index, version) {
+                    if parse_version(version).is_none() {
+                        report.errors.push(format!(
+                            "<<{}>> - {} {}: 版本号格式错误: {}",
+                            base_name,
+                            sheet_name,
+                            cell_name(cell_index, index + 1),
+                            version
+                        ));
+                        invalid_version = true;
+                    }
                 }
             }
+            if invalid_version {
+                table.filtered_rows += 1;
+                continue;
+            }
+            let released = |version: &&str| {
+                is_released(since_version.as_deref(), until_version.as_deref(), version)
+            };
+            if !versions.iter().any(released) {
+                table.filtered_rows += 1;
+                continue;
+            }
 
-            if line_limit > 0 && line_limit < 3 {
-                return Err(Error::new(
-                    io::ErrorKind::Other,
-                    format!(
-                        "CHECK SERVER CSV FILE: <<{}>> - {} ERROR: 行数限制不能小于3, 前3行是必须的头; 不限制请设置为0",
-                        base_name, sheet_name
-                    ),
-                ));
+            let mut values: Vec<String> = cell_indexes
+                .iter()
+                .map(|&i| row.get(i).map(|cell| cell.to_string()).unwrap_or_default())
+                .collect();
+            unicode_hygiene::check_row(
+                &table,
+                &mut values,
+                index + 1,
+                self.fix_unicode,
+                &mut report.warnings,
+            );
+            // 主键为空的行不导出
+            if values.first().is_none_or(|key| key.is_empty()) {
+                continue;
             }
 
-            let strip_quot = csv_name == "Language" || csv_name == "BadWords";
-            let ts_export = csv_name == "Language";
+            // 空单元格: 必填报错, 否则依次取表头默认值、规则文件默认值、类型默认值
+            for (column, value) in table.columns.iter().zip(values.iter_mut()).skip(1) {
+                if !value.trim().is_empty() {
+                    continue;
+                }
+                let column_type = &column.column_type;
+                let rule = self.rules.column(&table.meta.name, &column.name);
+                if column_type.required || rule.is_some_and(|r| r.required) {
+                    report.errors.push(format!(
+                        "<<{}>> - {} 第{}行 {}: 必填字段为空",
+                        base_name,
+                        sheet_name,
+                        index + 1,
+                        column.name
+                    ));
+                } else if let Some(default) = column_type
+                    .default
+                    .as_ref()
+                    .or_else(|| rule.and_then(|r| r.default.as_ref()))
+                {
+                    *value = default.clone();
+                } else {
+                    *value = column_type.type_default().to_string();
+                }
+            }
 
-            let server_csv = self.output_server_dir.join(format!("{}.csv", csv_name));
-            let client_csv = self.output_client_dir.join(format!("{}.csv", csv_name));
+            let mut max_width = None;
+            if let Some(cell_index) = max_width_index {
+                let width = row
+                    .get(cell_index)
+                    .map(|cell| cell.to_string())
+                    .unwrap_or_default();
+                let width = width.trim();
+                match width.parse() {
+                    Ok(width) => max_width = Some(width),
+                    Err(_) if width.is_empty() => {}
+                    Err(_) => report.warnings.push(format!(
+                        "<<{}>> - {} {}: 显示宽度不是整数: {}",
+                        base_name,
+                        sheet_name,
+                        cell_name(cell_index, index + 1),
+                        width
+                    )),
+                }
+            }
 
-            let mut server_content = String::new();
-            let mut client_content = String::new();
-            let mut ts_text = Vec::new();
+            table.rows.push(SheetRow {
+                values,
+                line: index + 1,
+                max_width,
+                since_version,
+                until_version,
+            });
+        }
 
-            let mut unexport_cell_index = std::collections::HashSet::new();
-            let mut title_mapping = std::collections::HashMap::new();
+        Ok(Some(table))
+    }
 
-            for (index, row) in rows.iter().enumerate() {
-                if line_limit > 0 && index + 1 > line_limit {
-                    break;
-                }
+    /// 导出目标的目标版本, 目标未单独配置时使用导出设置中的目标版本
+    fn version_for<'a>(&'a self, target: &'a ExportTarget) -> &'a str {
+        let version = target.target_version.trim();
+        if version.is_empty() {
+            &self.target_version
+        } else {
+            version
+        }
+    }
+}
 
-                let mut types_for_client = Vec::new();
-                let mut contents = Vec::new();
-                let mut data_mapping = if ts_export && index > 2 {
-                    Some(std::collections::HashMap::new())
-                } else {
-                    None
-                };
-                let mut row_key = String::new();
+/// 判断数据行在目标版本中是否已发布且未下线, 目标版本为空时不过滤
+fn is_released(since: Option<&str>, until: Option<&str>, version: &str) -> bool {
+    if version.is_empty() {
+        return true;
+    }
+    let released = since.is_none_or(|since| compare_version(since, version) != Ordering::Greater);
+    let retired = until.is_some_and(|until| compare_version(until, version) == Ordering::Less);
+    released && !retired
+}
 
-                for (cell_index, cell) in row.iter().enumerate() {
-                    let mut value = cell.to_string();
+/// 解析后的工作表, 只包含需要导出的列和行
+#[derive(Clone)]
+pub struct SheetTable {
+    pub meta: TableMeta,
+    pub source: String,
+    /// 工作簿相对Excel目录的路径和工作表名
+    pub workbook: String,
+    pub sheet_name: String,
+    /// 相对输出目录的子目录, 不保持目录结构时为空
+    pub output_dir: PathBuf,
+    pub columns: Vec<SheetColumn>,
+    pub rows: Vec<SheetRow>,
+    pub filtered_rows: usize,
+}
 
-                    if index == 1 {
-                        title_mapping.insert(cell_index, value.clone());
-                    }
+#[derive(Clone)]
+pub struct SheetColumn {
+    /// 第1行注释, 第一列为A1表选项原文
+    pub comment: String,
+    pub name: String,
+    pub column_type: ColumnType,
+    /// 在工作表中的列下标, 用于报告单元格坐标
+    pub cell_index: usize,
+}
 
-                    if index == 0 {
-                        value = value
-                            .replace(",", " ")
-                            .replace("\r\n", " ")
-                            .replace("\n", " ");
+#[derive(Clone)]
+pub struct SheetRow {
+    pub values: Vec<String>,
+    /// 在工作表中的行号, 从1开始
+    pub line: usize,
+    /// A1 中 `width_column=` 指定的列声明的显示宽度
+    pub max_width: Option<usize>,
+    /// SINCE_VERSION 和 UNTIL_VERSION 列的值
+    pub since_version: Option<String>,
+    pub until_version: Option<String>,
+}
 
-                        if value.starts_with("UNEXPORT_") || value.trim().is_empty() {
-                            unexport_cell_index.insert(cell_index);
-                            continue;
-                        }
-                    } else {
-                        value = value
-                            .replace(",", "，")
-                            .replace("\r\n", "\n")
-                            .replace("\n", "\\n");
-                    }
+impl SheetTable {
+    /// 只包含在目标版本中生效的行, 所有行都生效时不复制
+    pub fn released(&self, version: &str) -> Cow<'_, SheetTable> {
+        let released = |row: &SheetRow| {
+            is_released(
+                row.since_version.as_deref(),
+                row.until_version.as_deref(),
+                version,
+            )
+        };
+        if self.rows.iter().all(released) {
+            return Cow::Borrowed(self);
+        }
+        let mut table = self.clone();
+        table.rows.retain(released);
+        table.filtered_rows += self.rows.len() - table.rows.len();
+        Cow::Owned(table)
+    }
+}
 
-                    if unexport_cell_index.contains(&cell_index) {
-                        continue;
-                    }
+/// 单元格坐标, 例如 (0, 0) -> A1, (27, 4) -> AB5
+pub fn cell_name(cell_index: usize, line: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = cell_index + 1;
+    while n > 0 {
+        letters.push((b'A' + ((n - 1) % 26) as u8) as char);
+        n = (n - 1) / 26;
+    }
+    letters.iter().rev().collect::<String>() + &line.to_string()
+}
 
-                    if index == 1 && value.trim().is_empty() {
-                        unexport_cell_index.insert(cell_index);
-                        continue;
-                    }
+/// 标识符只能包含ASCII字母、数字和下划线, 且不能以数字开头
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
 
-                    if index > 2 && cell_index == 0 {
-                        row_key = value.clone();
-                    }
+fn table_format(table: &SheetTable, target: &ExportTarget) -> TableFormat {
+    table.meta.format.unwrap_or(target.format)
+}
 
-                    if index > 2 && ts_export && cell_index > 0 {
-                        if let Some(ref mut mapping) = data_mapping {
-                            if let Some(title) = title_mapping.get(&cell_index) {
-                                mapping.insert(title.clone(), value.clone());
-                            }
-                        }
-                    }
+/// 表在导出目标目录中的文件路径, 拆分的表为分块索引文件
+pub fn table_file(table: &SheetTable, target: &ExportTarget) -> PathBuf {
+    if is_chunked(table, target) {
+        return table.output_dir.join(table_chunks::index_file_name(table));
+    }
+    table.output_dir.join(format!(
+        "{}.{}",
+        table.meta.name,
+        table_format(table, target).extension()
+    ))
+}
 
-                    if index == 2 {
-                        if value.contains("#") {
-                            let values: Vec<&str> = value.split('#').collect();
-                            types_for_client.push(values[1].to_string());
-                            value = values[0].to_string();
-                        } else {
-                            types_for_client.push(value.clone());
-                        }
-                    }
+/// 表选项中配置了分块数的表只在客户端表头的导出目标中拆分
+fn is_chunked(table: &SheetTable, target: &ExportTarget) -> bool {
+    table.meta.chunks > 1 && target.header == HeaderStyle::Client
+}
 
-                    if csv_name == "BadWords" {
-                        value = value.replace("\"", "");
-                    }
+/// 表中导出到目标的列下标
+pub fn target_columns(table: &SheetTable, target: &ExportTarget) -> Vec<usize> {
+    (0..table.columns.len())
+        .filter(|&i| target.includes_column(i, &table.columns[i].column_type))
+        .collect()
+}
 
-                    if strip_quot
-                        && (value.contains("\\n") || value.contains(",") || value.contains("，"))
-                    {
-                        value = value.replace("\"", "\"\"");
-                        value = format!("\"{}\"", value);
-                    }
+/// 按导出目标写出一张表, 表选项中的格式优先于导出目标配置的格式;
+/// 拆分的表按主键范围写出多个分块文件和索引文件
+fn write_table(table: &SheetTable, target: &ExportTarget, dir: &path::Path) -> io::Result<()> {
+    let dir = dir.join(&table.output_dir);
+    fs::create_dir_all(&dir)?;
+    let columns = target_columns(table, target);
+    let format = table_format(table, target);
+    if !is_chunked(table, target) {
+        let rows: Vec<&SheetRow> = table.rows.iter().collect();
+        let content = table_content(table, &rows, &columns, target.header, format)?;
+        return fs::write(dir.join(table_file(table, target)), content);
+    }
 
-                    contents.push(value);
-                }
+    let chunks = table_chunks::split_rows(table, table.meta.chunks);
+    for (i, rows) in chunks.iter().enumerate() {
+        let content = table_content(table, rows, &columns, target.header, format)?;
+        fs::write(
+            dir.join(table_chunks::chunk_file_name(table, i, format.extension())),
+            content,
+        )?;
+    }
+    let index = table_chunks::chunk_index(table, &chunks, format.extension());
+    fs::write(
+        dir.join(table_chunks::index_file_name(table)),
+        serde_json::to_string_pretty(&index)?,
+    )
+}
 
-                let row_content = contents.join(",") + "\n";
-                if !row_content.starts_with(",") {
-                    server_content.push_str(&row_content);
-                }
-                if index > 0 {
-                    let client_row = if !types_for_client.is_empty() {
-                        types_for_client.join(",") + "\n"
-                    } else {
-                        row_content.clone()
-                    };
-                    if !client_row.starts_with(",") {
-                        client_content.push_str(&client_row);
-                    }
-                }
+fn table_content(
+    table: &SheetTable,
+    rows: &[&SheetRow],
+    columns: &[usize],
+    header: HeaderStyle,
+    format: TableFormat,
+) -> io::Result<String> {
+    match format {
+        TableFormat::Csv => Ok(table_csv(table, rows, columns, header)),
+        TableFormat::Json => {
+            let records: Vec<serde_json::Value> = rows
+                .iter()
+                .map(|row| {
+                    columns
+                        .iter()
+                        .map(|&i| {
+                            let column = &table.columns[i];
+                            (
+                                column.name.clone(),
+                                column.column_type.json_value(&row.values[i]),
+                            )
+                        })
+                        .collect::<serde_json::Map<_, _>>()
+                        .into()
+                })
+                .collect();
+            Ok(serde_json::to_string_pretty(&records)?)
+        }
+        TableFormat::Lua => Ok(lua_table::table_lua(table, rows, columns)),
+    }
+}
 
-                if ts_export && index > 2 {
-                    if let Some(mapping) = data_mapping {
-                        if let Ok(json_str) = serde_json::to_string(&mapping) {
-                            let json_str = json_str.replace("\\\\n", "\\n");
-                            ts_text.push(format!("\"{}\": {},", row_key, json_str));
-                        }
-                    }
-                }
-            }
+/// 表头为server样式时带注释行并使用服务器类型, client样式使用客户端类型
+fn table_csv(
+    table: &SheetTable,
+    rows: &[&SheetRow],
+    columns: &[usize],
+    header: HeaderStyle,
+) -> String {
+    let name = table.meta.name.as_str();
+    let strip_quot = name == LANGUAGE_TABLE || name == BAD_WORDS_TABLE;
+    let encode = |value: &str, comment_row: bool| {
+        let mut value = if comment_row {
+            value
+                .replace(",", " ")
+                .replace("\r\n", " ")
+                .replace("\n", " ")
+        } else {
+            escape_text(value)
+        };
+        if name == BAD_WORDS_TABLE {
+            value = value.replace("\"", "");
+        }
+        if strip_quot && (value.contains("\\n") || value.contains(",") || value.contains("，")) {
+            value = format!("\"{}\"", value.replace("\"", "\"\""));
+        }
+        value
+    };
 
-            if for_server {
-                fs::write(&server_csv, server_content)?;
-            }
+    let mut content = String::new();
+    let mut push_row = |cells: Vec<String>| {
+        let row_content = cells.join(",") + "\n";
+        if !row_content.starts_with(",") {
+            content.push_str(&row_content);
+        }
+    };
+    let header_row = |cell: &dyn Fn(&SheetColumn) -> String| {
+        columns.iter().map(|&i| cell(&table.columns[i])).collect()
+    };
+    if header == HeaderStyle::Server {
+        push_row(header_row(&|c| encode(&c.comment, true)));
+    }
+    push_row(header_row(&|c| encode(&c.name, false)));
+    push_row(header_row(&|c| match header {
+        HeaderStyle::Server => encode(&c.column_type.server, false),
+        HeaderStyle::Client => encode(&c.column_type.client, false),
+    }));
+    for row in rows {
+        push_row(
+            columns
+                .iter()
+                .map(|&i| encode(&row.values[i], false))
+                .collect(),
+        );
+    }
+    content
+}
 
-            if for_client {
-                fs::write(&client_csv, client_content)?;
-            }
+/// 数据单元格转义: 逗号转全角, 换行转成字面量\n
+pub fn escape_text(value: &str) -> String {
+    value
+        .replace(",", "，")
+        .replace("\r\n", "\n")
+        .replace("\n", "\\n")
+}
 
-            if ts_export && !ts_text.is_empty() {
-                let ts_content = format!(
-                    "let CXTranslationText: Record<string, Record<string, string>> = {{\n{}\n}};\nexport {{ CXTranslationText }};\n",
-                    ts_text.join("\n")
-                );
-                let ts_file = PathBuf::from(&self.output_client_dir).join("CXTranslationText.ts");
-                fs::write(ts_file, ts_content)?;
-            }
+/// 解析点分数字版本号, 可带v前缀; 有不是数字的段时返回 None
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .trim()
+        .trim_start_matches(['v', 'V'])
+        .split('.')
+        .map(|part| part.trim().parse().ok())
+        .collect()
+}
+
+/// 按点分数字比较版本号, 例如 1.2 < 1.10, 缺少的段视为0; 调用前需用 `parse_version` 检查格式
+fn compare_version(a: &str, b: &str) -> Ordering {
+    let (a, b) = (
+        parse_version(a).unwrap_or_default(),
+        parse_version(b).unwrap_or_default(),
+    );
+    for i in 0..a.len().max(b.len()) {
+        let ord = a.get(i).unwrap_or(&0).cmp(b.get(i).unwrap_or(&0));
+        if ord != Ordering::Equal {
+            return ord;
         }
+    }
+    Ordering::Equal
+}
 
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_parsing() {
+        assert_eq!(parse_version("1.2.0"), Some(vec![1, 2, 0]));
+        assert_eq!(parse_version(" v1.10 "), Some(vec![1, 10]));
+        assert_eq!(parse_version("next"), None);
+        assert_eq!(parse_version("1.x"), None);
+        assert_eq!(parse_version("TBD"), None);
+        assert_eq!(compare_version("1.2", "1.10"), Ordering::Less);
+        assert_eq!(compare_version("1.2", "1.2.0"), Ordering::Equal);
+    }
+
+    #[test]
+    fn released_rows() {
+        assert!(is_released(Some("1.2"), None, "1.2"));
+        assert!(!is_released(Some("1.3"), None, "1.2"));
+        assert!(!is_released(None, Some("1.1"), "1.2"));
+        assert!(is_released(None, Some("1.2"), "1.2"));
+        assert!(is_released(Some("9"), None, ""));
     }
 }